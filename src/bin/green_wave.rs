@@ -0,0 +1,77 @@
+use road_intersection::constants::*;
+use road_intersection::model::Location;
+use road_intersection::scenario::Scenario;
+use road_intersection::signal::{green_wave, SignalConfig};
+
+//prints offsets for the fixed plan of a scenario so cars driving towards one side at the
+//design speed meet green at every crossing
+fn main() {
+    let mut scenario = None;
+    let mut towards = Location::East;
+    let mut speed = CAR_SPEED_F64 * TICKS_PER_SECOND;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| {
+            eprintln!("Missing value for {}", arg);
+            std::process::exit(2);
+        });
+        match arg.as_str() {
+            "--scenario" => scenario = Some(value),
+            "--towards" => {
+                towards = Location::all()
+                    .into_iter()
+                    .find(|location| location.name() == value)
+                    .unwrap_or_else(|| {
+                        eprintln!("Unknown side: {}", value);
+                        std::process::exit(2);
+                    })
+            }
+            "--speed" => {
+                speed = value
+                    .parse()
+                    .ok()
+                    .filter(|speed| *speed > 0.0)
+                    .unwrap_or_else(|| {
+                        eprintln!("Invalid speed: {}", value);
+                        std::process::exit(2);
+                    })
+            }
+            _ => {
+                eprintln!("Unknown argument: {}", arg);
+                std::process::exit(2);
+            }
+        }
+    }
+    let Some(path) = scenario else {
+        eprintln!("Green wave needs --scenario");
+        std::process::exit(2);
+    };
+    let scenario = Scenario::load(&path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let SignalConfig::Fixed(plan) = scenario.signal else {
+        eprintln!("Green wave needs a fixed signal plan");
+        std::process::exit(1);
+    };
+    let plan = plan.normalize().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let network = &scenario.network;
+    let distances: Vec<f64> = (0..network.junctions())
+        .map(|index| network.distance_along(index, &towards))
+        .collect();
+    let offsets = green_wave(&distances, speed, plan.cycle_length());
+    println!(
+        "# green wave towards the {} at {} px/s, {} s cycle",
+        towards.name(),
+        speed,
+        plan.cycle_length()
+    );
+    let offsets: Vec<String> = offsets
+        .iter()
+        .map(|offset| format!("{:.1}", offset))
+        .collect();
+    println!("offsets = [{}]", offsets.join(", "));
+}
@@ -0,0 +1,37 @@
+use road_intersection::cli::Args;
+use road_intersection::metrics::write_trips;
+
+//runs a scenario as fast as possible without a window
+fn main() {
+    let args = Args::parse().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+    if args.scenario.is_none() {
+        eprintln!("Headless run needs --scenario");
+        std::process::exit(2);
+    }
+    let mut simulation = args.build_simulation().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    if simulation.is_finished() || !simulation.has_duration() {
+        eprintln!("Headless scenario must set a positive duration");
+        std::process::exit(1);
+    }
+    println!("Seed: {}", simulation.model.seed);
+
+    while !simulation.is_finished() {
+        simulation.advance();
+    }
+    println!("{}", simulation.kpis.summary());
+    println!("{} cars still on the road", simulation.model.cars.len());
+    println!("{} collisions", simulation.collisions.len());
+    if let Some(path) = &args.trips {
+        if let Err(e) = write_trips(path, &simulation.trips) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        println!("Wrote {} trips to {}", simulation.trips.len(), path);
+    }
+}
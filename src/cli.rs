@@ -0,0 +1,142 @@
+use crate::following::{CarFollowing, IdmParams};
+use crate::layout::JunctionKind;
+use crate::model::Model;
+use crate::scenario::Scenario;
+use crate::signal::{MaxPressureConfig, SignalConfig, SignalPlan, SignalTiming};
+use crate::simulation::Simulation;
+use std::env;
+
+#[derive(Debug, Default)]
+pub struct Args {
+    pub scenario: Option<String>,
+    pub seed: Option<u64>,
+    pub controller: Option<String>,
+    //car following model
+    pub following: Option<String>,
+    //signal or roundabout at every crossing
+    pub junction: Option<String>,
+    //file for per car trip records, csv or json lines
+    pub trips: Option<String>,
+    //stop the simulation when two cars collide
+    pub pause_on_collision: bool,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self, String> {
+        Self::parse_from(env::args().skip(1))
+    }
+
+    pub fn parse_from(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--scenario" => parsed.scenario = Some(Self::value(&arg, args.next())?),
+                "--trips" => parsed.trips = Some(Self::value(&arg, args.next())?),
+                "--controller" => parsed.controller = Some(Self::value(&arg, args.next())?),
+                "--pause-on-collision" => parsed.pause_on_collision = true,
+                "--following" => parsed.following = Some(Self::value(&arg, args.next())?),
+                "--junction" => parsed.junction = Some(Self::value(&arg, args.next())?),
+                "--seed" => {
+                    let value = Self::value(&arg, args.next())?;
+                    let seed = value
+                        .parse()
+                        .map_err(|_| format!("Invalid seed: {}", value))?;
+                    parsed.seed = Some(seed);
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    //load scenario and seed the model, --seed wins over the scenario seed
+    pub fn build_simulation(&self) -> Result<Simulation, String> {
+        let scenario = match &self.scenario {
+            Some(path) => Some(Scenario::load(path)?),
+            None => None,
+        };
+        let seed = self
+            .seed
+            .or(scenario.as_ref().and_then(|scenario| scenario.seed));
+        let model = match seed {
+            Some(seed) => Model::with_seed(seed),
+            None => Model::new(),
+        };
+        let mut signal = self.signal_config(scenario.as_ref())?;
+        let following = self.following(scenario.as_ref())?;
+        following.validate()?;
+        let mut simulation = match scenario {
+            Some(scenario) => Simulation::with_scenario(model, scenario),
+            None => Simulation::new(model),
+        };
+        //--junction swaps the crossings and keeps the demand
+        if let Some(name) = &self.junction {
+            let mut layout = simulation.model.config.layout.clone();
+            layout.junction = JunctionKind::parse(name)?;
+            layout.validate()?;
+            let network = simulation.model.config.network.clone();
+            network.validate(&layout)?;
+            simulation.model.set_roads(layout, network);
+        }
+        //cars sort out a roundabout among themselves, its lights stay dark
+        if simulation.model.config.layout.is_roundabout() {
+            signal = SignalConfig::Flashing;
+        }
+        simulation.traffic_controllers = simulation
+            .model
+            .junctions
+            .iter()
+            .map(|_| signal.build())
+            .collect::<Result<_, _>>()?;
+        simulation.model.set_timing(&signal.timing());
+        simulation.model.set_offsets(&signal.offsets());
+        simulation.model.config.following = following;
+        simulation.pause_on_collision = self.pause_on_collision;
+        Ok(simulation)
+    }
+
+    //--controller picks the controller, the plan still comes from the scenario,
+    //a fixed plan over a scenario without one gives every arm of its network green
+    fn signal_config(&self, scenario: Option<&Scenario>) -> Result<SignalConfig, String> {
+        let configured = scenario.map(|scenario| scenario.signal.clone());
+        match self.controller.as_deref() {
+            None => Ok(configured.unwrap_or_default()),
+            Some("actuated") => match configured {
+                Some(SignalConfig::Actuated(timing)) => Ok(SignalConfig::Actuated(timing)),
+                _ => Ok(SignalConfig::Actuated(SignalTiming::default())),
+            },
+            Some("fixed") => match configured {
+                Some(SignalConfig::Fixed(plan)) => Ok(SignalConfig::Fixed(plan)),
+                _ => Ok(SignalConfig::Fixed(match scenario {
+                    Some(scenario) => SignalPlan::for_network(&scenario.network),
+                    None => SignalPlan::default(),
+                })),
+            },
+            Some("max-pressure") => match configured {
+                Some(SignalConfig::MaxPressure(config)) => Ok(SignalConfig::MaxPressure(config)),
+                _ => Ok(SignalConfig::MaxPressure(MaxPressureConfig::default())),
+            },
+            Some("flashing") => Ok(SignalConfig::Flashing),
+            Some(name) => Err(format!("Unknown controller: {}", name)),
+        }
+    }
+
+    //--following picks the model, parameters still come from the scenario
+    fn following(&self, scenario: Option<&Scenario>) -> Result<CarFollowing, String> {
+        let configured = scenario.map(|scenario| scenario.following.clone());
+        match self.following.as_deref() {
+            None => Ok(configured.unwrap_or_default()),
+            Some("basic") => Ok(CarFollowing::Basic),
+            Some("idm") => match configured {
+                Some(CarFollowing::Idm(params)) => Ok(CarFollowing::Idm(params)),
+                _ => Ok(CarFollowing::Idm(IdmParams::default())),
+            },
+            Some(name) => Err(format!("Unknown car following model: {}", name)),
+        }
+    }
+
+    fn value(flag: &str, value: Option<String>) -> Result<String, String> {
+        value.ok_or(format!("Missing value for {}", flag))
+    }
+}
@@ -0,0 +1,154 @@
+use crate::constants::*;
+use crate::lane::Lane;
+use crate::model::{Car, PointF};
+
+//oriented bounding box of a car, axes are unit vectors along and across the car
+pub struct Obb {
+    pub center: PointF,
+    pub half_length: f64,
+    pub half_width: f64,
+    pub along: (f64, f64),
+    pub across: (f64, f64),
+}
+
+impl Obb {
+    pub fn of(car: &Car) -> Self {
+        let (sin, cos) = car.rad.sin_cos();
+        Self {
+            center: car.position.clone(),
+            half_length: car.size.length as f64 / 2.0,
+            half_width: car.size.width as f64 / 2.0,
+            along: (cos, sin),
+            across: (-sin, cos),
+        }
+    }
+
+    //corners in drawing order, starting front left
+    pub fn corners(&self) -> [PointF; 4] {
+        let (ax, ay) = (
+            self.along.0 * self.half_length,
+            self.along.1 * self.half_length,
+        );
+        let (cx, cy) = (
+            self.across.0 * self.half_width,
+            self.across.1 * self.half_width,
+        );
+        [(1.0, -1.0), (1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0)].map(|(a, c)| {
+            PointF::new(
+                self.center.x + a * ax + c * cx,
+                self.center.y + a * ay + c * cy,
+            )
+        })
+    }
+
+    //pixels along lane to the nearest part of the box less than half_width beside it,
+    //none if the box keeps out of that strip
+    pub fn nearest_along(&self, lane: &Lane, half_width: f64) -> Option<f64> {
+        let mut outline: Vec<(f64, f64)> = self
+            .corners()
+            .iter()
+            .map(|corner| (lane.along(corner), lane.aside(corner)))
+            .collect();
+        for side in [-1.0, 1.0] {
+            outline = clip(&outline, side, half_width - POSITION_TOLERANCE);
+        }
+        outline
+            .iter()
+            .map(|(along, _)| *along)
+            .min_by(f64::total_cmp)
+    }
+
+    //half of the box's shadow on axis
+    fn radius(&self, axis: (f64, f64)) -> f64 {
+        self.half_length * dot(self.along, axis).abs()
+            + self.half_width * dot(self.across, axis).abs()
+    }
+
+    //separating axis test, boxes that only touch do not overlap
+    pub fn overlaps(&self, other: &Obb) -> bool {
+        let offset = (
+            other.center.x - self.center.x,
+            other.center.y - self.center.y,
+        );
+        //boxes further apart than their corners reach are quickly told apart
+        let reach = self.half_length + self.half_width + other.half_length + other.half_width;
+        if dot(offset, offset) >= reach * reach {
+            return false;
+        }
+        [self.along, self.across, other.along, other.across]
+            .iter()
+            .all(|axis| {
+                dot(offset, *axis).abs()
+                    < self.radius(*axis) + other.radius(*axis) - POSITION_TOLERANCE
+            })
+    }
+}
+
+fn dot(a: (f64, f64), b: (f64, f64)) -> f64 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+//part of the outline of (along, aside) points with side * aside at most limit
+fn clip(outline: &[(f64, f64)], side: f64, limit: f64) -> Vec<(f64, f64)> {
+    let inside = |point: &(f64, f64)| side * point.1 <= limit;
+    let mut clipped = vec![];
+    for (index, point) in outline.iter().enumerate() {
+        let previous = &outline[(index + outline.len() - 1) % outline.len()];
+        if inside(point) != inside(previous) {
+            let t = (limit - side * previous.1) / (side * point.1 - side * previous.1);
+            clipped.push((
+                previous.0 + t * (point.0 - previous.0),
+                previous.1 + t * (point.1 - previous.1),
+            ));
+        }
+        if inside(point) {
+            clipped.push(*point);
+        }
+    }
+    clipped
+}
+
+//two cars that started to overlap, with their state at that tick
+#[derive(Clone, Debug)]
+pub struct Collision {
+    pub tick: u64,
+    pub cars: [Car; 2],
+}
+
+impl Collision {
+    pub fn new(tick: u64, first: &Car, second: &Car) -> Self {
+        Self {
+            tick,
+            cars: [first.clone(), second.clone()],
+        }
+    }
+
+    pub fn involves(&self, id: u64) -> bool {
+        self.cars.iter().any(|car| car.id == id)
+    }
+
+    pub fn describe(&self) -> String {
+        let cars: Vec<String> = self.cars.iter().map(Self::describe_car).collect();
+        format!(
+            "Collision at tick {} ({:.2} s): {}",
+            self.tick,
+            self.tick as f64 * TICK_DURATION,
+            cars.join(" / ")
+        )
+    }
+
+    fn describe_car(car: &Car) -> String {
+        format!(
+            "{} {} from {} turning {} heading {} at ({:.1}, {:.1}) {:.0} deg {:.1} px/s",
+            car.kind.name(),
+            car.id,
+            car.origin.name(),
+            car.turn.name(),
+            car.direction.name(),
+            car.position.x,
+            car.position.y,
+            car.deg,
+            car.speed * TICKS_PER_SECOND
+        )
+    }
+}
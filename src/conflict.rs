@@ -0,0 +1,184 @@
+use crate::collision::Obb;
+use crate::constants::*;
+use crate::following::CarFollowing;
+use crate::layout::Layout;
+use crate::model::{Car, Config, Destination, Junction, Location, SignalState, TrafficLightSwitch};
+use crate::network::Network;
+use crate::vehicle::VehicleKind;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+//lanes and arms with their angles a table was swept for
+type Swept = (Layout, Vec<(Location, f64)>);
+
+thread_local! {
+    //tables already swept, sweeping takes every movement through the crossing tick by tick,
+    //at most CONFLICT_TABLES_KEPT of them
+    static TABLES: RefCell<Vec<(Swept, Rc<ConflictTable>)>> = const { RefCell::new(vec![]) };
+}
+
+//one way through the crossing, long vehicles take it on a wider arc
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct Movement {
+    pub from: Location,
+    pub turn: Destination,
+    pub kind: VehicleKind,
+}
+
+impl Movement {
+    pub fn new(from: Location, turn: Destination, kind: VehicleKind) -> Self {
+        Self { from, turn, kind }
+    }
+
+    pub fn of(car: &Car) -> Self {
+        Self::new(car.origin.clone(), car.turn.clone(), car.kind)
+    }
+
+    pub fn all() -> Vec<Movement> {
+        let mut movements = vec![];
+        for from in Location::all() {
+            for turn in [Destination::Ahead, Destination::Left, Destination::Right] {
+                for kind in VehicleKind::all() {
+                    movements.push(Movement::new(from.clone(), turn.clone(), kind));
+                }
+            }
+        }
+        movements
+    }
+
+    //every movement open at crossing index of network
+    pub fn through(network: &Network, index: usize) -> Vec<Movement> {
+        let mut movements = vec![];
+        for from in network.arms(index) {
+            for turn in network.turns(index, &from.opposite()) {
+                for kind in VehicleKind::all() {
+                    movements.push(Movement::new(from.clone(), turn.clone(), kind));
+                }
+            }
+        }
+        movements
+    }
+
+    //who goes first where two movements cross: straight, then the near side turns,
+    //then the turns across oncoming traffic
+    pub fn priority(&self) -> u8 {
+        match self.turn {
+            Destination::Ahead => 2,
+            Destination::Left | Destination::BearLeft => 1,
+            Destination::Right | Destination::BearRight => 0,
+        }
+    }
+}
+
+//pairs of movements whose paths through the crossing touch from any of their lanes
+#[derive(Clone, Debug, Default)]
+pub struct ConflictTable {
+    conflicts: HashSet<(Movement, Movement)>,
+}
+
+impl ConflictTable {
+    //table for the lanes of layout and the arms of network, swept once and shared by every config
+    //with those, crossings on the four sides all look alike and skewed ones only come alone
+    pub fn shared(layout: &Layout, network: &Network) -> Rc<Self> {
+        let network = match network.is_skewed() {
+            true => network.clone(),
+            false => Network::default(),
+        };
+        let arms = network.arm_angles(0);
+        TABLES.with(|tables| {
+            let mut tables = tables.borrow_mut();
+            //the table last asked for goes to the back, the one unused longest is dropped first
+            if let Some(index) = tables
+                .iter()
+                .position(|((known, swept), _)| known == layout && *swept == arms)
+            {
+                let entry = tables.remove(index);
+                let table = entry.1.clone();
+                tables.push(entry);
+                return table;
+            }
+            //paths depend on the geometry only, so a single crossing with default following stands for all
+            let config = Config {
+                following: CarFollowing::default(),
+                layout: layout.clone(),
+                crossings: vec![network.crossing(0, layout)],
+                network,
+                conflicts: Rc::default(),
+            };
+            let table = Rc::new(Self::new(&config));
+            if tables.len() >= CONFLICT_TABLES_KEPT {
+                tables.remove(0);
+            }
+            tables.push(((layout.clone(), arms), table.clone()));
+            table
+        })
+    }
+
+    //drive every movement from each of its lanes on an empty road and compare the boxes it sweeps
+    pub fn new(config: &Config) -> Self {
+        let mut paths: Vec<(Movement, Vec<Obb>)> = vec![];
+        for movement in Movement::through(&config.network, 0) {
+            for index in config.layout.lanes_for(&movement.turn) {
+                let path = Self::sweep(&movement, index, config);
+                paths.push((movement.clone(), path));
+            }
+        }
+        let mut conflicts = HashSet::new();
+        for (first, first_path) in &paths {
+            for (second, second_path) in &paths {
+                //cars from the same approach keep to their lanes or follow each other
+                if first.from == second.from {
+                    continue;
+                }
+                let touch = first_path
+                    .iter()
+                    .any(|a| second_path.iter().any(|b| a.overlaps(b)));
+                if touch {
+                    conflicts.insert((first.clone(), second.clone()));
+                }
+            }
+        }
+        Self { conflicts }
+    }
+
+    //movements look the same at every crossing, the first one stands for all
+    fn sweep(movement: &Movement, index: usize, config: &Config) -> Vec<Obb> {
+        let mut junctions = Junction::create_junctions(config);
+        //paths depend on the geometry only, so every arm gets a green light
+        junctions[0].traffic_light_switch.traffic_lights =
+            TrafficLightSwitch::create_traffic_lights(&config.network.arms(0));
+        for light in junctions[0]
+            .traffic_light_switch
+            .traffic_lights
+            .values_mut()
+        {
+            light.set_state(SignalState::Green);
+        }
+        let lane = config
+            .layout
+            .approach(&config.crossings[0], &movement.from, index);
+        let mut car = Car::new(0, lane, movement.turn.clone(), movement.kind, config);
+        let mut path = vec![];
+        while !car.is_off_screen() {
+            car.drive(&[], &[], &junctions, config);
+            //long vehicles swing out of the box on their arc
+            if car.is_in_crossing(config) || car.is_turning() {
+                path.push(Obb::of(&car));
+            }
+        }
+        path
+    }
+
+    pub fn conflicts(&self, first: &Movement, second: &Movement) -> bool {
+        self.conflicts.contains(&(first.clone(), second.clone()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.conflicts.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
@@ -1,4 +1,4 @@
-pub const TITLE:&str = "crossroads";
+pub const TITLE: &str = "crossroads";
 pub const SCREEN_WIDTH: i32 = 800;
 pub const SCREEN_HEIGHT: i32 = 600;
 pub const MARGIN: i32 = 8;
@@ -10,11 +10,39 @@ pub const LANDSCAPE_URL: &str = "assets/images/corner.png";
 pub const BLUE_CAR_URL: &str = "assets/images/blue.png";
 pub const WHITE_CAR_URL: &str = "assets/images/white.png";
 pub const ORANGE_CAR_URL: &str = "assets/images/orange.png";
+pub const BLUE_BICYCLE_URL: &str = "assets/images/blue_bicycle.png";
+pub const WHITE_BICYCLE_URL: &str = "assets/images/white_bicycle.png";
+pub const ORANGE_BICYCLE_URL: &str = "assets/images/orange_bicycle.png";
+pub const BLUE_MOTORBIKE_URL: &str = "assets/images/blue_motorbike.png";
+pub const WHITE_MOTORBIKE_URL: &str = "assets/images/white_motorbike.png";
+pub const ORANGE_MOTORBIKE_URL: &str = "assets/images/orange_motorbike.png";
+pub const BLUE_TRUCK_URL: &str = "assets/images/blue_truck.png";
+pub const WHITE_TRUCK_URL: &str = "assets/images/white_truck.png";
+pub const ORANGE_TRUCK_URL: &str = "assets/images/orange_truck.png";
+pub const BLUE_BUS_URL: &str = "assets/images/blue_bus.png";
+pub const WHITE_BUS_URL: &str = "assets/images/white_bus.png";
+pub const ORANGE_BUS_URL: &str = "assets/images/orange_bus.png";
+pub const BLUE_AMBULANCE_URL: &str = "assets/images/blue_ambulance.png";
+pub const WHITE_AMBULANCE_URL: &str = "assets/images/white_ambulance.png";
+pub const ORANGE_AMBULANCE_URL: &str = "assets/images/orange_ambulance.png";
 pub const GO_SIGN_URL: &str = "assets/images/go.png";
 pub const STOP_SIGN_URL: &str = "assets/images/stop.png";
+pub const AMBER_SIGN_URL: &str = "assets/images/amber.png";
+pub const RED_AMBER_SIGN_URL: &str = "assets/images/red_amber.png";
 pub const CAR_COLOR_ORANGE: (u8, u8, u8) = (255, 165, 0);
 pub const CAR_COLOR_WHITE: (u8, u8, u8) = (255, 255, 255);
 pub const CAR_COLOR_BLUE: (u8, u8, u8) = (0, 0, 255);
+pub const SIGNAL_COLOR_GREEN: (u8, u8, u8) = (0, 255, 0);
+pub const SIGNAL_COLOR_RED: (u8, u8, u8) = (255, 0, 0);
+pub const SIGNAL_COLOR_AMBER: (u8, u8, u8) = (255, 176, 0);
+pub const SIGNAL_COLOR_RED_AMBER: (u8, u8, u8) = (255, 90, 0);
+pub const CRASH_COLOR: (u8, u8, u8) = (255, 0, 255);
+//pixels
+pub const CRASH_OUTLINE: i32 = 3;
+pub const HUD_TEXT_COLOR: (u8, u8, u8) = (255, 255, 255);
+pub const HUD_BACKGROUND: (u8, u8, u8, u8) = (0, 0, 0, 160);
+//screen pixels per font pixel
+pub const HUD_SCALE: i32 = 2;
 pub const TRAFFIC_LIGHTS_WIDTH: i32 = 18;
 pub const TRAFFIC_LIGHTS_HEIGTH: i32 = 18;
 pub const SCREEN_WIDTH_F64: f64 = SCREEN_WIDTH as f64;
@@ -22,9 +50,94 @@ pub const SCREEN_HEIGHT_F64: f64 = SCREEN_HEIGHT as f64;
 pub const CAR_SIZE_F64: f64 = CAR_SIZE as f64;
 pub const CAR_SPEED_F64: f64 = CAR_SPEED as f64;
 pub const MARGIN_F64: f64 = MARGIN as f64;
-pub const BREAK_POINT_WEST: f64 = (SCREEN_WIDTH / 2 - CAR_SIZE - MARGIN) as f64;
-pub const BREAK_POINT_EAST: f64 = (SCREEN_WIDTH / 2 + CAR_SIZE + MARGIN) as f64;
-pub const BREAK_POINT_NORTH: f64 = (SCREEN_HEIGHT / 2 - CAR_SIZE - MARGIN) as f64;
-pub const BREAK_POINT_SOUTH: f64 = (SCREEN_HEIGHT / 2 + CAR_SIZE + MARGIN) as f64;
-pub const MAX_CARS_IN_QUEUE: i32 = 7;
-pub const LINE_COLOR_2: (u8, u8, u8) = (100, 100, 100);
\ No newline at end of file
+//pixels across one lane
+pub const LANE_WIDTH: f64 = CAR_SIZE_F64 + MARGIN_F64;
+pub const MAX_LANES: usize = 3;
+//degrees, two arms of a crossing leave at least this far apart
+pub const MIN_ARM_GAP: f64 = 30.0;
+//degrees an arm may turn away from the compass point it is named after
+pub const MAX_ARM_SKEW: f64 = 45.0;
+//pixels of road between two crossings, room for two cars
+pub const MIN_ROAD_LENGTH: f64 = 2.0 * (CAR_SIZE_F64 + SEPARATION_DISTANCE);
+//share of the cars an approach holds that makes a long queue
+pub const LONG_QUEUE_SHARE: f64 = 0.8;
+pub const LINE_COLOR_2: (u8, u8, u8) = (100, 100, 100);
+pub const TICKS_PER_SECOND: f64 = 60.0;
+pub const TICK_DURATION: f64 = 1.0 / TICKS_PER_SECOND;
+pub const DEFAULT_GREEN_TIME: f64 = 10.0;
+pub const DEFAULT_AMBER_TIME: f64 = 3.0;
+pub const DEFAULT_ALL_RED_TIME: f64 = 2.0;
+pub const DEFAULT_RED_AMBER_TIME: f64 = 1.0;
+pub const DEFAULT_MIN_GREEN_TIME: f64 = 5.0;
+pub const DEFAULT_MAX_GREEN_TIME: f64 = 30.0;
+//seconds without a car reaching a detector before a green goes to a waiting phase
+pub const GAP_OUT_TIME: f64 = 3.0;
+pub const DEFAULT_DECISION_INTERVAL: f64 = 5.0;
+//seconds of history behind rolling KPIs
+pub const KPI_ROLLING_WINDOW: f64 = 300.0;
+//pixels per second squared
+pub const CAR_ACCELERATION: f64 = 30.0;
+pub const COMFORTABLE_DECELERATION: f64 = 60.0;
+//seconds
+pub const DEFAULT_TIME_HEADWAY: f64 = 1.0;
+//share of max acceleration an IDM car needs before it pulls away
+pub const IDM_START_THRESHOLD: f64 = 0.25;
+//pixels per second, a braking IDM car below this stands
+pub const IDM_STANDSTILL_SPEED: f64 = 1.0;
+//pixels, positions closer than this are the same
+pub const POSITION_TOLERANCE: f64 = 1e-6;
+//pixels sideways, cars closer than this to a lane block it
+pub const LANE_TOLERANCE: f64 = CAR_SIZE_F64;
+//pixels
+pub const DETECTOR_LENGTH: f64 = 1.0;
+//seconds, a turning car only goes if conflicting traffic needs at least this long to reach the stop line
+pub const CRITICAL_GAP: f64 = 4.0;
+//conflict tables kept for later configs on the same roads
+pub const CONFLICT_TABLES_KEPT: usize = 4;
+//pixels from the centre of a roundabout to the middle of its ring
+pub const ROUNDABOUT_RADIUS: f64 = 2.0 * LANE_WIDTH;
+//seconds, a car only joins the ring if circulating traffic needs at least this long to reach it
+pub const ROUNDABOUT_CRITICAL_GAP: f64 = 2.0;
+//pixels of a zebra crossing along its arm
+pub const ZEBRA_WIDTH: f64 = 16.0;
+pub const ZEBRA_COLOR: (u8, u8, u8) = (200, 200, 200);
+pub const PEDESTRIAN_SIZE: i32 = 8;
+pub const PEDESTRIAN_SIZE_F64: f64 = PEDESTRIAN_SIZE as f64;
+//pixels per second
+pub const PEDESTRIAN_SPEED: f64 = 12.0;
+pub const PEDESTRIAN_COLOR: (u8, u8, u8) = (255, 220, 180);
+pub const WALK_COLOR: (u8, u8, u8) = (255, 255, 255);
+pub const WALK_SIGNAL_SIZE: i32 = 6;
+//seconds someone waits at a zebra before actuated lights stop the cars for them
+pub const MAX_PEDESTRIAN_WAIT: f64 = 30.0;
+//pixels of a bicycle from front to back and side to side
+pub const BICYCLE_LENGTH: i32 = 16;
+pub const BICYCLE_WIDTH: i32 = 8;
+//pixels per tick
+pub const BICYCLE_SPEED: f64 = 0.5;
+//pixels per second squared
+pub const BICYCLE_ACCELERATION: f64 = 15.0;
+pub const MOTORBIKE_LENGTH: i32 = 16;
+pub const MOTORBIKE_WIDTH: i32 = 10;
+pub const MOTORBIKE_SPEED: f64 = 1.2;
+pub const MOTORBIKE_ACCELERATION: f64 = 45.0;
+pub const TRUCK_LENGTH: i32 = 40;
+pub const TRUCK_WIDTH: i32 = 22;
+pub const TRUCK_SPEED: f64 = 0.8;
+pub const TRUCK_ACCELERATION: f64 = 15.0;
+//pixels, the tightest arc a truck can drive
+pub const TRUCK_TURN_RADIUS: f64 = 40.0;
+pub const BUS_LENGTH: i32 = 48;
+pub const BUS_WIDTH: i32 = 22;
+pub const BUS_SPEED: f64 = 0.8;
+pub const BUS_ACCELERATION: f64 = 18.0;
+pub const BUS_TURN_RADIUS: f64 = 56.0;
+pub const EMERGENCY_LENGTH: i32 = 30;
+pub const EMERGENCY_WIDTH: i32 = 22;
+pub const EMERGENCY_SPEED: f64 = 1.4;
+pub const EMERGENCY_ACCELERATION: f64 = 40.0;
+//pixels before its stop line from where an emergency vehicle calls green for its approach
+pub const PREEMPTION_DISTANCE: f64 = 320.0;
+//pixels between the advanced stop line of a bike box and the car stop line behind it
+pub const BIKE_BOX_DEPTH: f64 = 24.0;
+pub const BIKE_BOX_COLOR: (u8, u8, u8) = (60, 170, 90);
@@ -1,174 +1,111 @@
 use crate::constants::*;
-use crate::model::{Destination, Location};
-use crate::Model;
-use crate::View;
+use crate::hud::hud_lines;
+use crate::model::Location;
+use crate::network::{Edge, Route};
+use crate::simulation::Simulation;
+use crate::vehicle::VehicleKind;
+use crate::view::View;
+use rand::Rng;
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 
 pub struct Controller {
-    model: Model,
+    simulation: Simulation,
     view: View,
+    pub show_hud: bool,
 }
 
 impl Controller {
-    pub fn new(model: Model, view: View) -> Self {
-        Self { model, view }
+    pub fn new(simulation: Simulation, view: View) -> Self {
+        Self {
+            simulation,
+            view,
+            show_hud: true,
+        }
     }
 
     pub fn tick(&mut self) {
-        let all_cars = self.model.cars.clone();
-        for car in &mut self.model.cars {
-            car.drive(
-                &all_cars,
-                &self.model.traffic_light_switch.traffic_lights,
-                &self.model.config,
-            );
+        self.simulation.step(TICK_DURATION);
+        //show live KPIs once a second
+        if self.simulation.tick.is_multiple_of(TICKS_PER_SECOND as u64) {
+            let title = format!("{} | {}", TITLE, self.simulation.kpis.live_line());
+            self.view.set_title(&title);
         }
-        //remove from list cars that are no longer on the screen
-        self.remove_old_cars();
-        //change traffic lights
-        self.control_traffic();
         //draw model
-        self.view.draw_model(&mut self.model);
-    }
-
-    fn remove_old_cars(&mut self) {
-        let clonned = self.model.cars.clone();
-        for (index, car) in clonned.iter().enumerate() {
-            if car.direction == Location::East && car.position.x > SCREEN_WIDTH_F64 {
-                self.model.cars.remove(index);
-                break;
-            }
-            if car.direction == Location::West && car.position.x < -CAR_SIZE_F64 {
-                self.model.cars.remove(index);
-                break;
-            }
-            if car.direction == Location::South && car.position.y > SCREEN_HEIGHT_F64 {
-                self.model.cars.remove(index);
-                break;
-            }
-            if car.direction == Location::North && car.position.y < -CAR_SIZE_F64 {
-                self.model.cars.remove(index);
-                break;
-            }
-        }
+        let hud = self.show_hud.then(|| hud_lines(&self.simulation));
+        let crashed = self.simulation.crashed_cars();
+        self.view
+            .draw_model(&self.simulation.model, hud.as_deref(), &crashed);
     }
 
     pub fn key_down(&mut self, event: Event) {
-        match event {
+        let mut kind = VehicleKind::Car;
+        let location = match event {
             Event::KeyDown {
                 keycode: Some(Keycode::DOWN),
                 ..
-            } => self
-                .model
-                .spawn_car(Location::North, Destination::get_random()),
+            } => Location::North,
             Event::KeyDown {
                 keycode: Some(Keycode::UP),
                 ..
-            } => self
-                .model
-                .spawn_car(Location::South, Destination::get_random()),
+            } => Location::South,
             Event::KeyDown {
                 keycode: Some(Keycode::LEFT),
                 ..
-            } => self
-                .model
-                .spawn_car(Location::East, Destination::get_random()),
+            } => Location::East,
             Event::KeyDown {
                 keycode: Some(Keycode::RIGHT),
                 ..
-            } => self
-                .model
-                .spawn_car(Location::West, Destination::get_random()),
+            } => Location::West,
             Event::KeyDown {
                 keycode: Some(Keycode::R),
                 ..
-            } => self
-                .model
-                .spawn_car(Location::get_random(), Destination::get_random()),
-            _ => {}
-        }
-    }
-
-    pub fn control_traffic(&mut self) {
-        //check for queues
-        let mut west = (0, Location::West);
-        let mut east = (0, Location::East);
-        let mut south = (0, Location::South);
-        let mut north = (0, Location::North);
-        for car in &self.model.cars {
-            if car.direction == Location::East
-                && car.position.x + CAR_SIZE_F64 / 2.0 <= BREAK_POINT_WEST
-            {
-                west.0 += CAR_SPEED;
-            }
-            if car.direction == Location::West
-                && car.position.x >= BREAK_POINT_EAST + CAR_SIZE_F64 / 2.0
-            {
-                east.0 += CAR_SPEED;
+            } => Location::get_random(&mut self.simulation.model.rng),
+            //an ambulance from a random side
+            Event::KeyDown {
+                keycode: Some(Keycode::E),
+                ..
+            } => {
+                kind = VehicleKind::Emergency;
+                Location::get_random(&mut self.simulation.model.rng)
             }
-            if car.direction == Location::South
-                && car.position.y + CAR_SIZE_F64 / 2.0 <= BREAK_POINT_NORTH
-            {
-                north.0 += CAR_SPEED;
+            Event::KeyDown {
+                keycode: Some(Keycode::H),
+                ..
+            } => {
+                self.show_hud = !self.show_hud;
+                return;
             }
-            if car.direction == Location::North
-                && car.position.y >= BREAK_POINT_SOUTH + CAR_SIZE_F64 / 2.0
-            {
-                south.0 += CAR_SPEED;
+            //pause or resume, also after a collision stopped the simulation
+            Event::KeyDown {
+                keycode: Some(Keycode::P),
+                ..
+            } => {
+                self.simulation.paused = !self.simulation.paused;
+                return;
             }
-        }
-
-        if west.0 >= MAX_CARS_IN_QUEUE {
-            self.model.traffic_light_switch.urgent_request(west.1);
+            _ => return,
+        };
+        let model = &mut self.simulation.model;
+        //any of the roads on that side, a turn into one of the arms of its crossing
+        let network = &model.config.network;
+        let road = model.rng.random_range(0..network.roads(&location));
+        let Some(junction) = network.entry(&location, road) else {
             return;
-        }
-        if east.0 >= MAX_CARS_IN_QUEUE {
-            self.model.traffic_light_switch.urgent_request(east.1);
-            return;
-        }
-        if north.0 >= MAX_CARS_IN_QUEUE {
-            self.model.traffic_light_switch.urgent_request(north.1);
-            return;
-        }
-        if south.0 >= MAX_CARS_IN_QUEUE {
-            self.model.traffic_light_switch.urgent_request(south.1);
-            return;
-        }
-        //Check approach Break Points
-        for car in &self.model.cars {
-            if car.direction == Location::East
-                && (car.position.x == BREAK_POINT_WEST - CAR_SIZE_F64 * 1.5
-                    || car.position.x == BREAK_POINT_WEST - CAR_SIZE_F64 / 2.0)
-            {
-                //Send signal to T/L switch
-                self.model.traffic_light_switch.request(Location::West);
-                break;
-            }
-            if car.direction == Location::West
-                && (car.position.x == BREAK_POINT_EAST + CAR_SIZE_F64 * 1.5
-                    || car.position.x == BREAK_POINT_EAST + CAR_SIZE_F64 / 2.0)
-            {
-                //Send signal to T/L switch
-                self.model.traffic_light_switch.request(Location::East);
-                break;
-            }
-            if car.direction == Location::South
-                && (car.position.y == BREAK_POINT_NORTH - CAR_SIZE_F64 * 1.5
-                    || car.position.y == BREAK_POINT_NORTH - CAR_SIZE_F64 / 2.0)
-            {
-                //Send signal to T/L switch
-                self.model.traffic_light_switch.request(Location::North);
-                break;
-            }
-            if car.direction == Location::North
-                && (car.position.y == BREAK_POINT_SOUTH + CAR_SIZE_F64 * 1.5
-                    || car.position.y == BREAK_POINT_SOUTH + CAR_SIZE_F64 / 2.0)
-            {
-                //Send signal to T/L switch
-                self.model.traffic_light_switch.request(Location::South);
-                break;
-            }
-        }
+        };
+        let destination = network.random_turn(junction, &location.opposite(), &mut model.rng);
+        let entry = Edge {
+            side: location,
+            road,
+        };
+        model.spawn_on_route(&entry, Route::turn(destination), kind);
+    }
+
+    pub fn simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.simulation.is_finished()
     }
 }
@@ -0,0 +1,133 @@
+use crate::constants::*;
+use crate::model::{Config, Location, Model};
+use crate::scenario::Scenario;
+use crate::signal::{Decision, Observation, SignalTiming};
+use crate::simulation::Simulation;
+
+//reward = throughput * cars that left - waiting * seconds cars stood still
+#[derive(Debug, Clone)]
+pub struct RewardConfig {
+    pub waiting: f64,
+    pub throughput: f64,
+}
+
+impl Default for RewardConfig {
+    fn default() -> Self {
+        Self {
+            waiting: 1.0,
+            throughput: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    //simulation seconds between two agent decisions
+    pub decision_interval: f64,
+    //episode ends after this many steps even if the scenario runs on
+    pub max_steps: Option<u64>,
+    pub reward: RewardConfig,
+    pub timing: SignalTiming,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            decision_interval: DEFAULT_DECISION_INTERVAL,
+            max_steps: None,
+            reward: RewardConfig::default(),
+            timing: SignalTiming::default(),
+        }
+    }
+}
+
+//gym style environment, the agent picks the approach that gets green
+pub struct Environment {
+    pub config: EnvConfig,
+    pub scenario: Scenario,
+    //roads of the scenario, built once and shared by every episode
+    pub roads: Config,
+    pub simulation: Simulation,
+    pub steps: u64,
+}
+
+impl Environment {
+    pub fn new(scenario: Scenario, config: EnvConfig) -> Result<Self, String> {
+        if !config.decision_interval.is_finite() || config.decision_interval < TICK_DURATION {
+            return Err(format!(
+                "decision interval must be finite and at least one tick ({} s)",
+                TICK_DURATION
+            ));
+        }
+        if scenario.duration.is_none() && config.max_steps.is_none() {
+            return Err(String::from(
+                "episode needs a scenario duration or max steps",
+            ));
+        }
+        config.timing.validate()?;
+        scenario.following.validate()?;
+        if scenario.network.junctions() > 1 {
+            return Err(String::from("the agent controls a single crossing"));
+        }
+        let roads = Config::new(
+            scenario.following.clone(),
+            scenario.layout.clone(),
+            scenario.network.clone(),
+        );
+        let simulation = Self::build(&scenario, &roads, &config, 0);
+        Ok(Self {
+            config,
+            scenario,
+            roads,
+            simulation,
+            steps: 0,
+        })
+    }
+
+    fn build(scenario: &Scenario, roads: &Config, config: &EnvConfig, seed: u64) -> Simulation {
+        let model = Model::with_config(seed, roads.clone());
+        let mut simulation = Simulation::on_roads(model, scenario.clone());
+        simulation.model.set_timing(&config.timing);
+        simulation
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.simulation = Self::build(&self.scenario, &self.roads, &self.config, seed);
+        self.steps = 0;
+        Observation::new(&self.simulation.model, 0, self.simulation.tick)
+    }
+
+    pub fn step(&mut self, action: Location) -> (Observation, f64, bool) {
+        let decision = Decision::Force(vec![action]);
+        let ticks = (self.config.decision_interval / TICK_DURATION).round() as u64;
+        let throughput = self.simulation.throughput;
+        let mut waiting = 0.0;
+        let mut observation = None;
+        for _ in 0..ticks {
+            observation = self.simulation.advance_with(&decision).pop();
+            let stopped = self
+                .simulation
+                .model
+                .cars
+                .iter()
+                .filter(|car| car.is_stopped);
+            waiting += stopped.count() as f64 * TICK_DURATION;
+            if self.simulation.is_finished() {
+                break;
+            }
+        }
+        self.steps += 1;
+        let exited = (self.simulation.throughput - throughput) as f64;
+        let reward = self.config.reward.throughput * exited - self.config.reward.waiting * waiting;
+        let done = self.simulation.is_finished()
+            || self.config.max_steps.is_some_and(|max| self.steps >= max);
+        let observation = observation
+            .unwrap_or_else(|| Observation::new(&self.simulation.model, 0, self.simulation.tick));
+        (observation, reward, done)
+    }
+
+    //approaches the agent can give green, the arms of the crossing
+    pub fn action_space(&self) -> Vec<Location> {
+        self.scenario.network.arms(0)
+    }
+}
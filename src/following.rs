@@ -0,0 +1,223 @@
+use crate::constants::*;
+use crate::vehicle::VehicleKind;
+use serde::Deserialize;
+
+//how a car picks its speed from what is in front of it
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(tag = "model", rename_all = "lowercase")]
+pub enum CarFollowing {
+    //speed up to CAR_SPEED while braking comfortably still stops the car in time
+    #[default]
+    Basic,
+    //intelligent driver model
+    Idm(IdmParams),
+}
+
+//what a car has to stop for, pixels and pixels per tick
+pub enum Obstacle {
+    //car ahead, bumper to bumper
+    Car { gap: f64, speed: f64 },
+    //pixels left to the point where the car has to stand
+    StopLine { distance: f64 },
+}
+
+impl CarFollowing {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            CarFollowing::Basic => Ok(()),
+            CarFollowing::Idm(params) => params.validate(),
+        }
+    }
+
+    //pixels between standing cars in a queue
+    pub fn standstill_gap(&self) -> f64 {
+        match self {
+            CarFollowing::Basic => SEPARATION_DISTANCE,
+            CarFollowing::Idm(params) => params.min_gap,
+        }
+    }
+
+    //speed a vehicle of kind settles at on an empty road, pixels per tick
+    pub fn free_speed(&self, kind: VehicleKind) -> f64 {
+        match self {
+            CarFollowing::Basic => kind.max_speed(),
+            CarFollowing::Idm(params) => params.scaled(kind).desired_speed * TICK_DURATION,
+        }
+    }
+
+    //speed in pixels per tick for the next tick of a vehicle of kind
+    pub fn next_speed(&self, speed: f64, kind: VehicleKind, obstacles: &[Obstacle]) -> f64 {
+        match self {
+            CarFollowing::Basic => Self::basic_speed(speed, kind, obstacles),
+            CarFollowing::Idm(params) => params.scaled(kind).next_speed(speed, obstacles),
+        }
+    }
+
+    fn basic_speed(speed: f64, kind: VehicleKind, obstacles: &[Obstacle]) -> f64 {
+        let deceleration = COMFORTABLE_DECELERATION * TICK_DURATION * TICK_DURATION;
+        //room to brake in and room to move this tick
+        let mut gap = f64::INFINITY;
+        let mut reach = f64::INFINITY;
+        for obstacle in obstacles {
+            match obstacle {
+                Obstacle::Car {
+                    gap: car_gap,
+                    speed: car_speed,
+                } => {
+                    let room = car_gap - SEPARATION_DISTANCE;
+                    //leader brakes no harder than we do, so its braking distance is ours too
+                    gap = gap.min(room + car_speed * car_speed / (2.0 * deceleration));
+                    reach = reach.min(room);
+                }
+                Obstacle::StopLine { distance } => {
+                    gap = gap.min(*distance);
+                    reach = reach.min(*distance);
+                }
+            }
+        }
+        let safe_speed = (2.0 * deceleration * gap.max(0.0)).sqrt();
+        (speed + kind.acceleration() * TICK_DURATION * TICK_DURATION)
+            .min(kind.max_speed())
+            .min(safe_speed)
+            .min(reach.max(0.0))
+    }
+}
+
+//intelligent driver model parameters in pixels and seconds
+#[derive(Deserialize, Debug, Clone)]
+pub struct IdmParams {
+    #[serde(default = "IdmParams::default_desired_speed")]
+    pub desired_speed: f64,
+    #[serde(default = "IdmParams::default_time_headway")]
+    pub time_headway: f64,
+    //bumper to bumper gap when standing
+    #[serde(default = "IdmParams::default_min_gap")]
+    pub min_gap: f64,
+    #[serde(default = "IdmParams::default_max_acceleration")]
+    pub max_acceleration: f64,
+    #[serde(default = "IdmParams::default_comfortable_deceleration")]
+    pub comfortable_deceleration: f64,
+    #[serde(default = "IdmParams::default_exponent")]
+    pub exponent: f64,
+}
+
+impl Default for IdmParams {
+    fn default() -> Self {
+        Self {
+            desired_speed: Self::default_desired_speed(),
+            time_headway: Self::default_time_headway(),
+            min_gap: Self::default_min_gap(),
+            max_acceleration: Self::default_max_acceleration(),
+            comfortable_deceleration: Self::default_comfortable_deceleration(),
+            exponent: Self::default_exponent(),
+        }
+    }
+}
+
+impl IdmParams {
+    fn default_desired_speed() -> f64 {
+        CAR_SPEED_F64 * TICKS_PER_SECOND
+    }
+
+    fn default_time_headway() -> f64 {
+        DEFAULT_TIME_HEADWAY
+    }
+
+    fn default_min_gap() -> f64 {
+        SEPARATION_DISTANCE
+    }
+
+    fn default_max_acceleration() -> f64 {
+        CAR_ACCELERATION
+    }
+
+    fn default_comfortable_deceleration() -> f64 {
+        COMFORTABLE_DECELERATION
+    }
+
+    fn default_exponent() -> f64 {
+        4.0
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let params = [
+            self.desired_speed,
+            self.time_headway,
+            self.min_gap,
+            self.max_acceleration,
+            self.comfortable_deceleration,
+            self.exponent,
+        ];
+        if params.iter().any(|param| !param.is_finite()) {
+            return Err(String::from("car following parameters must be finite"));
+        }
+        if self.desired_speed <= 0.0
+            || self.max_acceleration <= 0.0
+            || self.comfortable_deceleration <= 0.0
+            || self.exponent <= 0.0
+        {
+            return Err(String::from(
+                "desired speed, accelerations and exponent must be positive",
+            ));
+        }
+        if self.time_headway < 0.0 || self.min_gap < 0.0 {
+            return Err(String::from(
+                "time headway and minimum gap cannot be negative",
+            ));
+        }
+        Ok(())
+    }
+
+    //gap the driver wants at speed v closing in on the obstacle at dv, pixels per second
+    fn desired_gap(&self, v: f64, dv: f64) -> f64 {
+        let braking = (self.max_acceleration * self.comfortable_deceleration).sqrt();
+        self.min_gap + (v * self.time_headway + v * dv / (2.0 * braking)).max(0.0)
+    }
+
+    //parameters for a vehicle of kind, the ones for cars scaled by its top speed and acceleration
+    pub fn scaled(&self, kind: VehicleKind) -> Self {
+        Self {
+            desired_speed: self.desired_speed * kind.max_speed() / CAR_SPEED_F64,
+            max_acceleration: self.max_acceleration * kind.acceleration() / CAR_ACCELERATION,
+            ..self.clone()
+        }
+    }
+
+    //pixels per second squared
+    pub fn acceleration(&self, v: f64, obstacles: &[Obstacle]) -> f64 {
+        let free = 1.0 - (v / self.desired_speed).powf(self.exponent);
+        let mut interaction: f64 = 0.0;
+        for obstacle in obstacles {
+            //stop lines are standing cars min_gap beyond the line
+            let (gap, dv) = match obstacle {
+                Obstacle::Car { gap, speed } => (*gap, v - speed * TICKS_PER_SECOND),
+                Obstacle::StopLine { distance } => (distance + self.min_gap, v),
+            };
+            let ratio = self.desired_gap(v, dv) / gap.max(f64::EPSILON);
+            interaction = interaction.max(ratio * ratio);
+        }
+        self.max_acceleration * (free - interaction)
+    }
+
+    fn next_speed(&self, speed: f64, obstacles: &[Obstacle]) -> f64 {
+        let v = speed * TICKS_PER_SECOND;
+        let acceleration = self.acceleration(v, obstacles);
+        //a standing car waits until it may pull away properly instead of creeping
+        if speed == 0.0 && acceleration < self.max_acceleration * IDM_START_THRESHOLD {
+            return 0.0;
+        }
+        let next = (v + acceleration * TICK_DURATION) * TICK_DURATION;
+        if acceleration < 0.0 && next < IDM_STANDSTILL_SPEED * TICK_DURATION {
+            return 0.0;
+        }
+        //never closer than min_gap to the car ahead, the discrete update can overshoot it
+        let reach = obstacles
+            .iter()
+            .map(|obstacle| match obstacle {
+                Obstacle::Car { gap, .. } => gap - self.min_gap,
+                Obstacle::StopLine { distance } => *distance,
+            })
+            .fold(f64::INFINITY, f64::min);
+        next.min(reach.max(0.0))
+    }
+}
@@ -0,0 +1,99 @@
+use crate::constants::*;
+use crate::model::Location;
+use crate::signal::Observation;
+use crate::simulation::Simulation;
+
+//text shown on top of the crossing
+pub fn hud_lines(simulation: &Simulation) -> Vec<String> {
+    let model = &simulation.model;
+    let controller = match model.config.layout.is_roundabout() {
+        true => "none, roundabout",
+        false => simulation
+            .traffic_controllers
+            .first()
+            .map_or("none", |controller| controller.name()),
+    };
+    let mut lines = vec![
+        format!("tick {}  time {:.1} s", simulation.tick, simulation.time()),
+        format!(
+            "cars {}  spawn rate {:.2}/s",
+            model.cars.len(),
+            simulation.spawn_rate()
+        ),
+        format!("controller {}", controller),
+        format!(
+            "collisions {}{}",
+            simulation.collisions.len(),
+            if simulation.paused { "  paused" } else { "" }
+        ),
+    ];
+    if model.config.layout.zebras {
+        let walking = model.pedestrians.iter().filter(|p| p.walking).count();
+        lines.push(format!(
+            "pedestrians {}  walking {}",
+            model.pedestrians.len(),
+            walking
+        ));
+    }
+    //queue and how long each light has shown its state
+    if model.junctions.len() == 1 {
+        let observation = Observation::new(model, 0, simulation.tick);
+        let lights = &model.junctions[0].traffic_light_switch.traffic_lights;
+        for location in Location::compass() {
+            //a side without an arm has no light
+            let Some(light) = lights.get(&location) else {
+                continue;
+            };
+            let mut line = format!(
+                "{:<10}queue {:<3}{} {:.1} s",
+                location.name(),
+                observation.queues[&location],
+                light.state.name(),
+                light.elapsed_time()
+            );
+            //zebra behind the light
+            if model.config.layout.zebras {
+                let walk = &model.junctions[0].traffic_light_switch.walk;
+                if let Some(signal) = walk.get(&location) {
+                    line.push_str(&format!("  walk {}", signal.name()));
+                }
+            }
+            lines.push(line);
+        }
+        if let Some(preemption) = &model.junctions[0].traffic_light_switch.preemption {
+            lines.push(format!(
+                "preempted for {} {:.1} s",
+                preemption.approach.name(),
+                preemption.elapsed as f64 * TICK_DURATION
+            ));
+        }
+        return lines;
+    }
+    //one line per crossing with queue and light of every approach
+    for (index, junction) in model.junctions.iter().enumerate() {
+        let observation = Observation::new(model, index, simulation.tick);
+        let approaches: Vec<String> = Location::compass()
+            .iter()
+            .filter_map(|location| {
+                let light = junction.traffic_light_switch.traffic_lights.get(location)?;
+                Some(format!(
+                    "{} {} {}",
+                    location.short_name(),
+                    observation.queues[location],
+                    light.state.name()
+                ))
+            })
+            .collect();
+        let preempted = match &junction.traffic_light_switch.preemption {
+            Some(preemption) => format!("  preempted {}", &preemption.approach.name()[..1]),
+            None => String::new(),
+        };
+        lines.push(format!(
+            "#{:<2}{}{}",
+            index,
+            approaches.join("  "),
+            preempted
+        ));
+    }
+    lines
+}
@@ -0,0 +1,105 @@
+use crate::constants::*;
+use crate::model::{Car, Location, PointF};
+
+//straight line cars drive along, positions on it are pixels from where it enters the screen
+#[derive(Clone, Debug)]
+pub struct Lane {
+    pub direction: Location,
+    //where the lane enters the screen
+    pub start: PointF,
+    //unit vector cars drive along it, y grows down the screen
+    pub heading: (f64, f64),
+}
+
+impl Lane {
+    //lane driven along heading towards direction running through point
+    pub fn new(direction: Location, heading: (f64, f64), point: &PointF) -> Self {
+        let (dx, dy) = heading;
+        //back from point to the edge of the screen it comes in over,
+        //lanes along the sides of the screen start exactly on it
+        let start = match (dx, dy) {
+            (_, 0.0) => PointF::new(if dx > 0.0 { 0.0 } else { SCREEN_WIDTH_F64 }, point.y),
+            (0.0, _) => PointF::new(point.x, if dy > 0.0 { 0.0 } else { SCREEN_HEIGHT_F64 }),
+            _ => {
+                let back = |at: f64, towards: f64, size: f64| match towards > 0.0 {
+                    true => at / towards,
+                    false => (at - size) / towards,
+                };
+                let back =
+                    back(point.x, dx, SCREEN_WIDTH_F64).min(back(point.y, dy, SCREEN_HEIGHT_F64));
+                PointF::new(point.x - dx * back, point.y - dy * back)
+            }
+        };
+        Self {
+            direction,
+            start,
+            heading,
+        }
+    }
+
+    //lane straight towards direction running through point
+    pub fn through(direction: Location, point: &PointF) -> Self {
+        let heading = direction.heading();
+        Self::new(direction, heading, point)
+    }
+
+    //lane the car is driving along right now
+    pub fn of(car: &Car) -> Self {
+        Self::new(car.direction.clone(), car.heading(), &car.position)
+    }
+
+    //unit vector of degrees clockwise on screen from east,
+    //exact along the sides of the screen so lanes there keep whole pixels
+    pub fn unit(degrees: f64) -> (f64, f64) {
+        match degrees.rem_euclid(360.0) {
+            0.0 => (1.0, 0.0),
+            90.0 => (0.0, 1.0),
+            180.0 => (-1.0, 0.0),
+            270.0 => (0.0, -1.0),
+            degrees => {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                (cos, sin)
+            }
+        }
+    }
+
+    //pixels driven along the lane to reach point
+    pub fn along(&self, point: &PointF) -> f64 {
+        let (dx, dy) = self.heading;
+        (point.x - self.start.x) * dx + (point.y - self.start.y) * dy
+    }
+
+    //pixels point lies beside the lane, to its right looking along it
+    pub fn aside(&self, point: &PointF) -> f64 {
+        let (dx, dy) = self.heading;
+        (point.y - self.start.y) * dx - (point.x - self.start.x) * dy
+    }
+
+    //point sideways close enough that a car there blocks the lane
+    pub fn covers(&self, point: &PointF) -> bool {
+        self.aside(point).abs() < LANE_TOLERANCE
+    }
+
+    //pixels other lane, running the same way, lies beside this one
+    pub fn beside(&self, other: &Lane) -> f64 {
+        self.aside(&other.start)
+    }
+
+    //pixels along the lane where it meets other, none if they run side by side
+    pub fn meets(&self, other: &Lane) -> Option<f64> {
+        let (dx, dy) = self.heading;
+        let (ox, oy) = other.heading;
+        //how fast the lane closes in on other per pixel driven
+        let closing = dy * ox - dx * oy;
+        if closing.abs() < 1e-9 {
+            return None;
+        }
+        Some(-other.aside(&self.start) / closing)
+    }
+
+    //point along pixels down the lane
+    pub fn point(&self, along: f64) -> PointF {
+        let (dx, dy) = self.heading;
+        PointF::new(self.start.x + dx * along, self.start.y + dy * along)
+    }
+}
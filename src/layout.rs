@@ -0,0 +1,491 @@
+use crate::constants::*;
+use crate::lane::Lane;
+use crate::model::{Destination, Location, PointF};
+use crate::vehicle::VehicleKind;
+use serde::Deserialize;
+
+//lanes every approach has, listed from the kerb to the centre line with the turns each allows,
+//cars leave the crossing on the lane as far from the centre line as the one they came in on
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Layout {
+    pub lanes: Vec<Vec<Destination>>,
+    pub junction: JunctionKind,
+    //zebra crossing on every arm just outside the box, cars stop before it
+    pub zebras: bool,
+    //advanced stop line for bicycles at the edge of the crossing with the car stop line behind it
+    pub bike_boxes: bool,
+}
+
+//how cars get through the crossings, the same at every crossing
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JunctionKind {
+    //cross with traffic lights
+    #[default]
+    Signal,
+    //one lane running clockwise round an island, cars give way to the ring when they join
+    Roundabout,
+}
+
+impl JunctionKind {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "signal" => Ok(JunctionKind::Signal),
+            "roundabout" => Ok(JunctionKind::Roundabout),
+            _ => Err(format!("Unknown junction: {}", name)),
+        }
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            lanes: vec![vec![
+                Destination::Left,
+                Destination::Ahead,
+                Destination::Right,
+            ]],
+            junction: JunctionKind::default(),
+            zebras: false,
+            bike_boxes: false,
+        }
+    }
+}
+
+impl Layout {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.lanes.is_empty() || self.lanes.len() > MAX_LANES {
+            return Err(format!(
+                "an approach needs 1 to {} lanes, got {}",
+                MAX_LANES,
+                self.lanes.len()
+            ));
+        }
+        if self.lanes.iter().any(|turns| turns.is_empty()) {
+            return Err(String::from("every lane needs at least one turn"));
+        }
+        for turn in [Destination::Left, Destination::Ahead, Destination::Right] {
+            if !self.lanes.iter().any(|turns| turns.contains(&turn)) {
+                return Err(format!("no lane for turning {}", turn.name()));
+            }
+        }
+        //paths through the crossing must not cross those of the neighbouring lane
+        for pair in self.lanes.windows(2) {
+            let kerb_side = pair[0].iter().map(Self::rank).max();
+            let centre_side = pair[1].iter().map(Self::rank).min();
+            if kerb_side > centre_side {
+                return Err(String::from(
+                    "lanes must go from left turns at the kerb to right turns at the centre line",
+                ));
+            }
+        }
+        if self.is_roundabout() && self.lanes.len() != 1 {
+            return Err(format!(
+                "a roundabout takes one lane per approach, got {}",
+                self.lanes.len()
+            ));
+        }
+        if self.is_roundabout() && self.bike_boxes {
+            return Err(String::from(
+                "a roundabout has no stop lines for bike boxes",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_roundabout(&self) -> bool {
+        self.junction == JunctionKind::Roundabout
+    }
+
+    //order of the turns from the kerb to the centre line
+    fn rank(turn: &Destination) -> u8 {
+        match turn {
+            Destination::Left => 0,
+            Destination::BearLeft => 1,
+            Destination::Ahead => 2,
+            Destination::BearRight => 3,
+            Destination::Right => 4,
+        }
+    }
+
+    //pixels from the centre of the crossing to its edges
+    pub fn half_width(&self) -> f64 {
+        match self.junction {
+            JunctionKind::Signal => self.lanes.len() as f64 * LANE_WIDTH,
+            //the box holds the ring with its lane
+            JunctionKind::Roundabout => ROUNDABOUT_RADIUS + LANE_WIDTH / 2.0,
+        }
+    }
+
+    //pixels of a zebra crossing along its arm, none without zebras
+    pub fn zebra_width(&self) -> f64 {
+        if self.zebras {
+            ZEBRA_WIDTH
+        } else {
+            0.0
+        }
+    }
+
+    //pixels of a bike box along its arm, none without bike boxes
+    pub fn bike_box_depth(&self) -> f64 {
+        if self.bike_boxes {
+            BIKE_BOX_DEPTH
+        } else {
+            0.0
+        }
+    }
+
+    //pixels a vehicle of kind waits behind the stop line it turns from, cars keep out of the bike box
+    pub fn hold_back(&self, kind: VehicleKind) -> f64 {
+        match kind {
+            VehicleKind::Bicycle => 0.0,
+            _ => self.bike_box_depth(),
+        }
+    }
+
+    //pixels across an arm from its centre line to the kerb
+    pub fn road_half_width(&self) -> f64 {
+        self.lanes.len() as f64 * LANE_WIDTH
+    }
+
+    //crossing at center with arms leaving at the given degrees, the box reaches out along every arm
+    //to where the kerbs of its neighbours meet it, the box of a roundabout holds the ring
+    pub fn crossing(&self, center: PointF, mut arms: Vec<(Location, f64)>) -> Crossing {
+        arms.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let kerb = self.road_half_width();
+        //pixels out along both arms where the kerbs between them meet, none if they lie in line
+        let corner = |from: f64, to: f64| {
+            if (to - from).rem_euclid(360.0) >= 180.0 {
+                return 0.0;
+            }
+            let (ax, ay) = Lane::unit(from);
+            let (bx, by) = Lane::unit(to);
+            kerb * (1.0 + ax * bx + ay * by) / (ax * by - ay * bx)
+        };
+        let count = arms.len();
+        let edges: Vec<f64> = (0..count)
+            .map(|index| match self.junction {
+                JunctionKind::Signal => {
+                    let angle = arms[index].1;
+                    let before = arms[(index + count - 1) % count].1;
+                    let after = arms[(index + 1) % count].1;
+                    corner(before, angle).max(corner(angle, after))
+                }
+                JunctionKind::Roundabout => self.half_width(),
+            })
+            .collect();
+        let side = match self.junction {
+            JunctionKind::Signal => kerb,
+            JunctionKind::Roundabout => self.half_width(),
+        };
+        let outline = arms
+            .iter()
+            .zip(&edges)
+            .flat_map(|((_, angle), edge)| {
+                let (dx, dy) = Lane::unit(*angle);
+                let x = center.x + dx * edge;
+                let y = center.y + dy * edge;
+                //anticlockwise kerb of the arm first
+                [
+                    PointF::new(x + dy * side, y - dx * side),
+                    PointF::new(x - dy * side, y + dx * side),
+                ]
+            })
+            .collect();
+        Crossing {
+            center,
+            arms,
+            edges,
+            outline,
+        }
+    }
+
+    //pixels from the centre of the crossing to a vehicle of kind waiting on lane with its front
+    //on the edge of the zebra, long vehicles wait further back to make a near side turn onto exit
+    //on the arc they can drive, far side turns are wide enough already
+    fn setback(&self, crossing: &Crossing, lane: &Lane, kind: VehicleKind, exit: &Location) -> f64 {
+        let origin = lane.direction.opposite();
+        let front = crossing.edge(&origin) + self.zebra_width() + kind.length() as f64 / 2.0;
+        let bend = crossing.bend(&origin, exit);
+        match self.corner(crossing, lane, exit) {
+            Some(corner) if bend < 0.0 => {
+                front.max(kind.turn_radius() * Self::tan_half(bend) + corner)
+            }
+            _ => front,
+        }
+    }
+
+    //pixels before the centre along lane where it meets the exit lane as far from the centre line,
+    //none going straight on
+    fn corner(&self, crossing: &Crossing, lane: &Lane, exit: &Location) -> Option<f64> {
+        let index = self.index_of(crossing, lane)?;
+        let out = self.departure(crossing, exit, index);
+        lane.meets(&out)
+            .map(|along| lane.along(&crossing.center) - along)
+    }
+
+    //pixels past the stop line of lane a vehicle of kind drives straight on before its arc onto
+    //exit, far side turns which would only reach the exit lane beyond the edge of the box hold
+    //their arc back until it ends where a vehicle leaving the box straight would be
+    fn lead(&self, crossing: &Crossing, lane: &Lane, kind: VehicleKind, exit: &Location) -> f64 {
+        if crossing.bend(&lane.direction.opposite(), exit) <= 0.0 {
+            return 0.0;
+        }
+        let (Some(index), Some(corner)) = (
+            self.index_of(crossing, lane),
+            self.corner(crossing, lane, exit),
+        ) else {
+            return 0.0;
+        };
+        let out = self.departure(crossing, exit, index);
+        let meet = lane.point(lane.along(&crossing.center) - corner);
+        let limit = out.along(&crossing.center) - out.along(&meet)
+            + crossing.edge(exit)
+            + self.zebra_width()
+            + kind.length() as f64 / 2.0;
+        match self.setback(crossing, lane, kind, exit) - corner - limit {
+            lead if lead > POSITION_TOLERANCE => lead,
+            _ => 0.0,
+        }
+    }
+
+    //tangent of half of degrees, exactly one for right angles so the arcs of square crossings
+    //keep whole pixel radii
+    fn tan_half(degrees: f64) -> f64 {
+        match degrees.abs() {
+            90.0 => 1.0,
+            degrees => (degrees / 2.0).to_radians().tan(),
+        }
+    }
+
+    //lane index cars from location drive in on towards crossing, 0 is the kerb lane
+    pub fn approach(&self, crossing: &Crossing, location: &Location, index: usize) -> Lane {
+        let heading = Lane::unit(crossing.inbound(location));
+        self.lane(crossing, location.opposite(), heading, index)
+    }
+
+    //lane index cars leave crossing on along the arm on side, 0 is the kerb lane
+    pub fn departure(&self, crossing: &Crossing, side: &Location, index: usize) -> Lane {
+        let heading = crossing.heading(side);
+        self.lane(crossing, side.clone(), heading, index)
+    }
+
+    fn lane(
+        &self,
+        crossing: &Crossing,
+        direction: Location,
+        heading: (f64, f64),
+        index: usize,
+    ) -> Lane {
+        //traffic keeps left, so the lanes lie left of the driving direction
+        let (dx, dy) = heading;
+        let aside = (self.lanes.len() - index) as f64 * LANE_WIDTH - LANE_WIDTH / 2.0;
+        let center = &crossing.center;
+        let point = PointF::new(center.x + dy * aside, center.y - dx * aside);
+        Lane::new(direction, heading, &point)
+    }
+
+    //indexes of the lanes a car taking turn may use
+    pub fn lanes_for(&self, turn: &Destination) -> Vec<usize> {
+        (0..self.lanes.len())
+            .filter(|index| self.lanes[*index].contains(turn))
+            .collect()
+    }
+
+    //index of the approach lane towards crossing that lane runs along
+    pub fn index_of(&self, crossing: &Crossing, lane: &Lane) -> Option<usize> {
+        (0..self.lanes.len()).find(|index| {
+            let approach = self.approach(crossing, &lane.direction.opposite(), *index);
+            approach.beside(lane).abs() < LANE_WIDTH / 2.0
+        })
+    }
+
+    //pixels along lane where the centre of a vehicle of kind leaving on exit stands waiting
+    //at the stop line of crossing, the advanced one of the bike box if there is one
+    pub fn stop_line(
+        &self,
+        crossing: &Crossing,
+        lane: &Lane,
+        kind: VehicleKind,
+        exit: &Location,
+    ) -> f64 {
+        lane.along(&crossing.center) - self.setback(crossing, lane, kind, exit)
+    }
+
+    //pixels along lane where a vehicle of kind starts its arc onto exit, at or past the stop line
+    pub fn turn_start(
+        &self,
+        crossing: &Crossing,
+        lane: &Lane,
+        kind: VehicleKind,
+        exit: &Location,
+    ) -> f64 {
+        self.stop_line(crossing, lane, kind, exit) + self.lead(crossing, lane, kind, exit)
+    }
+
+    //radius of the arc from the turn start on lane onto the exit lane as far from the centre line,
+    //touching both, near side turns curl round the corner and far side ones swing past the centre,
+    //infinite going straight on
+    pub fn turn_radius(
+        &self,
+        crossing: &Crossing,
+        lane: &Lane,
+        kind: VehicleKind,
+        exit: &Location,
+    ) -> f64 {
+        let bend = crossing.bend(&lane.direction.opposite(), exit);
+        match self.corner(crossing, lane, exit) {
+            Some(corner) if bend != 0.0 => {
+                let lead = self.lead(crossing, lane, kind, exit);
+                (self.setback(crossing, lane, kind, exit) - corner - lead) / Self::tan_half(bend)
+            }
+            _ => f64::INFINITY,
+        }
+    }
+
+    //point on the exit lane where the arc from the turn start on lane ends
+    pub fn turn_end(
+        &self,
+        crossing: &Crossing,
+        lane: &Lane,
+        kind: VehicleKind,
+        exit: &Location,
+    ) -> PointF {
+        let start = lane.point(self.turn_start(crossing, lane, kind, exit));
+        let bend = crossing.bend(&lane.direction.opposite(), exit);
+        //the arc leaves its start and reaches the exit lane as far from where the lanes meet
+        let reach = self.turn_radius(crossing, lane, kind, exit) * Self::tan_half(bend);
+        let (dx, dy) = lane.heading;
+        let (sx, sy) = crossing.heading(exit);
+        PointF::new(start.x + reach * (dx + sx), start.y + reach * (dy + sy))
+    }
+
+    //pixels along lane where its centre line meets the middle of the ring of the roundabout
+    //at center, coming in before the centre and going out after it
+    pub fn ring_join(&self, center: &PointF, lane: &Lane) -> f64 {
+        lane.along(center) - Self::ring_reach(center, lane)
+    }
+
+    pub fn ring_leave(&self, center: &PointF, lane: &Lane) -> f64 {
+        lane.along(center) + Self::ring_reach(center, lane)
+    }
+
+    fn ring_reach(center: &PointF, lane: &Lane) -> f64 {
+        let aside = lane.aside(center);
+        (ROUNDABOUT_RADIUS * ROUNDABOUT_RADIUS - aside * aside)
+            .max(0.0)
+            .sqrt()
+    }
+
+    //radians of point round center, growing clockwise on screen the way the ring runs
+    pub fn ring_angle(center: &PointF, point: &PointF) -> f64 {
+        (point.y - center.y).atan2(point.x - center.x)
+    }
+
+    //point in the middle of the ring at angle
+    pub fn ring_point(center: &PointF, angle: f64) -> PointF {
+        PointF::new(
+            center.x + ROUNDABOUT_RADIUS * angle.cos(),
+            center.y + ROUNDABOUT_RADIUS * angle.sin(),
+        )
+    }
+}
+
+//one crossing with the arms it has and the box they meet in, built once with the roads
+#[derive(Clone, Debug)]
+pub struct Crossing {
+    pub center: PointF,
+    //sides with an arm and the degrees it leaves the centre at, clockwise on screen from east
+    pub arms: Vec<(Location, f64)>,
+    //pixels from the centre along each arm to the edge of the box, in the order of arms
+    pub edges: Vec<f64>,
+    //corners of the box clockwise, two where each arm meets it
+    pub outline: Vec<PointF>,
+}
+
+impl Crossing {
+    //degrees the arm on side leaves the centre at, sides without one as the compass has them
+    pub fn angle(&self, side: &Location) -> f64 {
+        self.arms
+            .iter()
+            .find(|(arm, _)| arm == side)
+            .map_or_else(|| side.degrees(), |(_, angle)| *angle)
+    }
+
+    //degrees cars coming in on the arm on side drive at
+    pub fn inbound(&self, side: &Location) -> f64 {
+        (self.angle(side) + 180.0).rem_euclid(360.0)
+    }
+
+    //unit vector out of the crossing along the arm on side
+    pub fn heading(&self, side: &Location) -> (f64, f64) {
+        Lane::unit(self.angle(side))
+    }
+
+    //degrees a car coming in from origin turns through to leave on exit, clockwise on screen,
+    //negative for turns to the near side
+    pub fn bend(&self, origin: &Location, exit: &Location) -> f64 {
+        let bend = (self.angle(exit) - self.inbound(origin)).rem_euclid(360.0);
+        if bend > 180.0 {
+            bend - 360.0
+        } else {
+            bend
+        }
+    }
+
+    //pixels from the centre along the arm on side to the edge of the box,
+    //sides without an arm end where the box reaches furthest
+    pub fn edge(&self, side: &Location) -> f64 {
+        match self.arms.iter().position(|(arm, _)| arm == side) {
+            Some(index) => self.edges[index],
+            None => self.edges.iter().copied().fold(0.0, f64::max),
+        }
+    }
+
+    //any point strictly inside the box, a car on the stop line is not, the box bends in where
+    //the stop line of a short arm meets the kerb of a longer one
+    pub fn is_inside(&self, point: &PointF) -> bool {
+        let corners = self.outline.len();
+        let mut inside = false;
+        for index in 0..corners {
+            let from = &self.outline[index];
+            let to = &self.outline[(index + 1) % corners];
+            let (dx, dy) = (to.x - from.x, to.y - from.y);
+            let length = dx.hypot(dy);
+            //arms meeting kerb to kerb share a corner
+            if length < POSITION_TOLERANCE {
+                continue;
+            }
+            let share = (((point.x - from.x) * dx + (point.y - from.y) * dy) / (length * length))
+                .clamp(0.0, 1.0);
+            let (nearest_x, nearest_y) = (from.x + share * dx, from.y + share * dy);
+            if (point.x - nearest_x).hypot(point.y - nearest_y) <= POSITION_TOLERANCE {
+                return false;
+            }
+            //count the sides a ray towards growing x crosses
+            if (from.y > point.y) != (to.y > point.y)
+                && point.x < from.x + (point.y - from.y) * dx / dy
+            {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    //unit vector across the arm on side, the way x grows or y on east and west arms
+    pub fn normal(&self, side: &Location) -> (f64, f64) {
+        let (dx, dy) = self.heading(side);
+        if -dy > 0.0 || (dy == 0.0 && dx > 0.0) {
+            (-dy, dx)
+        } else {
+            (dy, -dx)
+        }
+    }
+
+    //where point lies across the arm on side, x on north and south arms, y on east and west ones
+    pub fn across(&self, side: &Location, point: &PointF) -> f64 {
+        let (nx, ny) = self.normal(side);
+        point.x * nx + point.y * ny
+    }
+}
@@ -0,0 +1,25 @@
+pub mod cli;
+pub mod collision;
+pub mod conflict;
+pub mod constants;
+pub mod env;
+pub mod following;
+pub mod hud;
+pub mod lane;
+pub mod layout;
+pub mod metrics;
+pub mod model;
+pub mod network;
+pub mod pedestrian;
+pub mod scenario;
+pub mod signal;
+pub mod simulation;
+pub mod vehicle;
+
+//SDL front-end
+#[cfg(feature = "sdl")]
+pub mod controller;
+#[cfg(feature = "sdl")]
+pub mod font;
+#[cfg(feature = "sdl")]
+pub mod view;
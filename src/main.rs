@@ -1,14 +1,11 @@
+use road_intersection::cli::Args;
+use road_intersection::constants::TITLE;
+use road_intersection::controller::Controller;
+use road_intersection::metrics::write_trips;
+use road_intersection::view::View;
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use std::time::Duration;
-mod controller;
-mod model;
-mod view;
-mod constants;
-use controller::Controller;
-use model::Model;
-use view::*;
-use crate::constants::TITLE;
 
 //Memo
 // Ahead - white
@@ -16,6 +13,16 @@ use crate::constants::TITLE;
 // Right - orange
 
 fn main() {
+    let args = Args::parse().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+    let simulation = args.build_simulation().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    println!("Seed: {}", simulation.model.seed);
+
     let sdl_context = sdl2::init().unwrap();
     let mut event_pump = sdl_context.event_pump().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
@@ -25,11 +32,9 @@ fn main() {
         .build()
         .unwrap();
     let canvas = window.into_canvas().build().unwrap();
-    
-    let view = View::new(canvas, (0, 0, 0));
-    let model = Model::new();
-    let mut controller = Controller::new(model, view);
 
+    let view = View::new(canvas, (0, 0, 0));
+    let mut controller = Controller::new(simulation, view);
 
     'running: loop {
         for event in event_pump.poll_iter() {
@@ -42,8 +47,18 @@ fn main() {
                 _ => controller.key_down(event),
             }
         }
-        controller.tick();       
+        controller.tick();
+        if controller.is_finished() {
+            break 'running;
+        }
         ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
     }
-   
+
+    println!("{}", controller.simulation().kpis.summary());
+    println!("{} collisions", controller.simulation().collisions.len());
+    if let Some(path) = &args.trips {
+        if let Err(e) = write_trips(path, &controller.simulation().trips) {
+            eprintln!("{}", e);
+        }
+    }
 }
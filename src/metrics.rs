@@ -0,0 +1,396 @@
+use crate::constants::*;
+use crate::following::CarFollowing;
+use crate::model::{Car, Destination, Location, SignalState};
+use crate::pedestrian::Pedestrian;
+use crate::signal::Observation;
+use crate::vehicle::VehicleKind;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+//everything recorded about one car once it has left the screen
+#[derive(Debug, Clone)]
+pub struct Trip {
+    pub id: u64,
+    //sides of the screen the car came in from and left on and its turn at the last crossing
+    pub origin: Location,
+    pub exit: Location,
+    pub turn: Destination,
+    pub spawn_tick: u64,
+    pub exit_tick: u64,
+    //ticks standing before the stop line
+    pub stopped_ticks: u64,
+    //ticks inside the crossing box
+    pub crossing_ticks: u64,
+    //ticks lost against driving the same way at full speed
+    pub delay_ticks: u64,
+    //times the car came to a standstill
+    pub stops: u64,
+    pub vehicle: VehicleKind,
+}
+
+impl Trip {
+    pub fn new(car: &Car, exit_tick: u64, following: &CarFollowing) -> Self {
+        Self {
+            id: car.id,
+            origin: car.entry.clone(),
+            exit: car.direction.clone(),
+            turn: car.turn.clone(),
+            spawn_tick: (exit_tick + 1).saturating_sub(car.age),
+            exit_tick,
+            stopped_ticks: car.queue_ticks,
+            crossing_ticks: car.crossing_ticks,
+            delay_ticks: car
+                .age
+                .saturating_sub((car.distance / following.free_speed(car.kind)).round() as u64),
+            stops: car.stops,
+            vehicle: car.kind,
+        }
+    }
+
+    //seconds from spawn to exit
+    pub fn travel_time(&self) -> f64 {
+        (self.exit_tick - self.spawn_tick) as f64 * TICK_DURATION
+    }
+
+    pub fn stopped_time(&self) -> f64 {
+        self.stopped_ticks as f64 * TICK_DURATION
+    }
+
+    pub fn crossing_time(&self) -> f64 {
+        self.crossing_ticks as f64 * TICK_DURATION
+    }
+
+    pub fn delay(&self) -> f64 {
+        self.delay_ticks as f64 * TICK_DURATION
+    }
+
+    fn csv_header() -> &'static str {
+        "id,origin,exit,turn,spawn_tick,exit_tick,travel_time,stopped_time,crossing_time,delay,stops,vehicle"
+    }
+
+    fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{:.3},{:.3},{:.3},{:.3},{},{}",
+            self.id,
+            self.origin.name(),
+            self.exit.name(),
+            self.turn.name(),
+            self.spawn_tick,
+            self.exit_tick,
+            self.travel_time(),
+            self.stopped_time(),
+            self.crossing_time(),
+            self.delay(),
+            self.stops,
+            self.vehicle.name()
+        )
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"id\":{},\"origin\":\"{}\",\"exit\":\"{}\",\"turn\":\"{}\",\"spawn_tick\":{},\"exit_tick\":{},\"travel_time\":{:.3},\"stopped_time\":{:.3},\"crossing_time\":{:.3},\"delay\":{:.3},\"stops\":{},\"vehicle\":\"{}\"}}",
+            self.id,
+            self.origin.name(),
+            self.exit.name(),
+            self.turn.name(),
+            self.spawn_tick,
+            self.exit_tick,
+            self.travel_time(),
+            self.stopped_time(),
+            self.crossing_time(),
+            self.delay(),
+            self.stops,
+            self.vehicle.name()
+        )
+    }
+}
+
+//csv for .csv files, json lines for everything else
+pub fn write_trips(path: &str, trips: &[Trip]) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Cannot create {}: {}", path, e))?;
+    let mut writer = BufWriter::new(file);
+    let csv = path.ends_with(".csv");
+    let mut write = || -> std::io::Result<()> {
+        if csv {
+            writeln!(writer, "{}", Trip::csv_header())?;
+        }
+        for trip in trips {
+            if csv {
+                writeln!(writer, "{}", trip.to_csv())?;
+            } else {
+                writeln!(writer, "{}", trip.to_json())?;
+            }
+        }
+        writer.flush()
+    };
+    write().map_err(|e| format!("Cannot write {}: {}", path, e))
+}
+
+//intersection KPIs collected while the simulation runs
+#[derive(Debug, Default)]
+pub struct Kpis {
+    pub ticks: u64,
+    pub throughput: HashMap<Location, u64>,
+    //exit ticks inside the rolling window
+    recent_exits: HashMap<Location, VecDeque<u64>>,
+    pub delays: Vec<f64>,
+    //longest queue on each approach of any crossing
+    pub max_queue: HashMap<Location, usize>,
+    //ticks each approach was green, summed over the crossings
+    pub green_ticks: HashMap<Location, u64>,
+    //ticks summed over the crossings
+    pub junction_ticks: u64,
+    //sides with an arm at any crossing
+    arms: HashSet<Location>,
+    //standstills of the cars that came in from each side
+    pub stops: HashMap<Location, u64>,
+    //vehicles served of each kind
+    pub vehicles: HashMap<VehicleKind, u64>,
+    //people who got across a zebra and seconds they waited at the kerb
+    pub pedestrians: u64,
+    pub pedestrian_wait: f64,
+    //emergency vehicles that called green and seconds from each call its approach took to show it,
+    //calls the vehicle drove through on before green are not answered
+    pub preemption_calls: u64,
+    pub preemptions: Vec<f64>,
+}
+
+impl Kpis {
+    pub fn record_tick(&mut self, observations: &[Observation]) {
+        self.ticks += 1;
+        for observation in observations {
+            self.junction_ticks += 1;
+            self.arms.extend(observation.lights.keys().cloned());
+            for (location, queue) in &observation.queues {
+                let max = self.max_queue.entry(location.clone()).or_insert(0);
+                *max = (*max).max(*queue);
+            }
+            for (location, state) in &observation.lights {
+                if *state == SignalState::Green {
+                    *self.green_ticks.entry(location.clone()).or_insert(0) += 1;
+                }
+            }
+        }
+        let window_start = self.ticks.saturating_sub(Self::window_ticks());
+        for exits in self.recent_exits.values_mut() {
+            while exits.front().is_some_and(|tick| *tick < window_start) {
+                exits.pop_front();
+            }
+        }
+    }
+
+    pub fn record_trip(&mut self, trip: &Trip) {
+        *self.throughput.entry(trip.origin.clone()).or_insert(0) += 1;
+        self.recent_exits
+            .entry(trip.origin.clone())
+            .or_default()
+            .push_back(trip.exit_tick);
+        self.delays.push(trip.delay());
+        *self.stops.entry(trip.origin.clone()).or_insert(0) += trip.stops;
+        *self.vehicles.entry(trip.vehicle).or_insert(0) += 1;
+    }
+
+    pub fn record_crossing(&mut self, pedestrian: &Pedestrian) {
+        self.pedestrians += 1;
+        self.pedestrian_wait += pedestrian.waiting_time();
+    }
+
+    pub fn record_call(&mut self) {
+        self.preemption_calls += 1;
+    }
+
+    pub fn record_preemption(&mut self, response: f64) {
+        self.preemptions.push(response);
+    }
+
+    fn window_ticks() -> u64 {
+        (KPI_ROLLING_WINDOW / TICK_DURATION) as u64
+    }
+
+    fn hours(ticks: u64) -> f64 {
+        ticks as f64 * TICK_DURATION / 3600.0
+    }
+
+    //cars per hour over the whole run
+    pub fn throughput_rate(&self, location: &Location) -> f64 {
+        if self.ticks == 0 {
+            return 0.0;
+        }
+        self.throughput.get(location).copied().unwrap_or(0) as f64 / Self::hours(self.ticks)
+    }
+
+    //cars per hour over the rolling window
+    pub fn rolling_throughput_rate(&self, location: &Location) -> f64 {
+        let window = self.ticks.min(Self::window_ticks());
+        if window == 0 {
+            return 0.0;
+        }
+        let exits = self
+            .recent_exits
+            .get(location)
+            .map_or(0, |exits| exits.len());
+        exits as f64 / Self::hours(window)
+    }
+
+    pub fn mean_delay(&self) -> f64 {
+        if self.delays.is_empty() {
+            return 0.0;
+        }
+        self.delays.iter().sum::<f64>() / self.delays.len() as f64
+    }
+
+    //nearest rank percentile
+    pub fn delay_percentile(&self, percentile: f64) -> f64 {
+        if self.delays.is_empty() {
+            return 0.0;
+        }
+        let mut delays = self.delays.clone();
+        delays.sort_by(|a, b| a.total_cmp(b));
+        let rank = (percentile / 100.0 * delays.len() as f64).ceil() as usize;
+        delays[rank.clamp(1, delays.len()) - 1]
+    }
+
+    //share of time the approach was green, averaged over the crossings
+    pub fn green_share(&self, location: &Location) -> f64 {
+        if self.junction_ticks == 0 {
+            return 0.0;
+        }
+        self.green_ticks.get(location).copied().unwrap_or(0) as f64 / self.junction_ticks as f64
+    }
+
+    //standstills per car that came in from location
+    pub fn stops_per_car(&self, location: &Location) -> f64 {
+        let cars = self.throughput.get(location).copied().unwrap_or(0);
+        if cars == 0 {
+            return 0.0;
+        }
+        self.stops.get(location).copied().unwrap_or(0) as f64 / cars as f64
+    }
+
+    //standstills per car over all cars served
+    pub fn mean_stops(&self) -> f64 {
+        if self.delays.is_empty() {
+            return 0.0;
+        }
+        self.stops.values().sum::<u64>() as f64 / self.delays.len() as f64
+    }
+
+    //seconds a person waited at the kerb on average
+    pub fn mean_pedestrian_wait(&self) -> f64 {
+        if self.pedestrians == 0 {
+            return 0.0;
+        }
+        self.pedestrian_wait / self.pedestrians as f64
+    }
+
+    //seconds emergency vehicles waited for green on average
+    pub fn mean_preemption_response(&self) -> f64 {
+        if self.preemptions.is_empty() {
+            return 0.0;
+        }
+        self.preemptions.iter().sum::<f64>() / self.preemptions.len() as f64
+    }
+
+    pub fn max_queue(&self, location: &Location) -> usize {
+        self.max_queue.get(location).copied().unwrap_or(0)
+    }
+
+    //one line for the window title
+    pub fn live_line(&self) -> String {
+        let rolling = self
+            .approaches()
+            .iter()
+            .map(|location| {
+                format!(
+                    "{} {:.0}",
+                    location.short_name(),
+                    self.rolling_throughput_rate(location)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "veh/h {} | delay mean {:.1} s p95 {:.1} s | stops {:.2}/car",
+            rolling,
+            self.mean_delay(),
+            self.delay_percentile(95.0),
+            self.mean_stops()
+        )
+    }
+
+    //sides with an arm at any crossing in compass order
+    fn approaches(&self) -> Vec<Location> {
+        Location::compass()
+            .into_iter()
+            .filter(|location| self.arms.contains(location))
+            .collect()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut lines = vec![format!(
+            "Simulated {:.1} s, {} cars served",
+            self.ticks as f64 * TICK_DURATION,
+            self.delays.len()
+        )];
+        lines.push(format!(
+            "Delay: mean {:.2} s, p95 {:.2} s",
+            self.mean_delay(),
+            self.delay_percentile(95.0)
+        ));
+        lines.push(format!("Stops: {:.2} per car", self.mean_stops()));
+        //only worth a line once anything but cars is about
+        if self.vehicles.keys().any(|kind| *kind != VehicleKind::Car) {
+            let vehicles = VehicleKind::all()
+                .iter()
+                .filter_map(|kind| {
+                    let served = self.vehicles.get(kind)?;
+                    Some(format!("{} {}", served, kind.name()))
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(format!("Vehicles: {}", vehicles));
+        }
+        if self.pedestrians > 0 {
+            lines.push(format!(
+                "Pedestrians: {} crossed, wait mean {:.2} s",
+                self.pedestrians,
+                self.mean_pedestrian_wait()
+            ));
+        }
+        if self.preemption_calls > 0 {
+            lines.push(format!(
+                "Preemptions: {} calls, {} answered, response mean {:.2} s, max {:.2} s",
+                self.preemption_calls,
+                self.preemptions.len(),
+                self.mean_preemption_response(),
+                self.preemptions.iter().copied().fold(0.0, f64::max)
+            ));
+        }
+        let approaches = self.approaches();
+        //as wide as the longest name, the ones off the corners outgrow the heading
+        let width = approaches
+            .iter()
+            .map(|location| location.name().len())
+            .chain(["approach".len()])
+            .max()
+            .unwrap_or(0);
+        lines.push(format!(
+            "{:<width$}{:>10}{:>12}{:>14}{:>11}{:>8}{:>11}",
+            "approach", "cars", "veh/h", "veh/h (last)", "max queue", "green", "stops/car"
+        ));
+        for location in approaches {
+            lines.push(format!(
+                "{:<width$}{:>10}{:>12.0}{:>14.0}{:>11}{:>7.1}%{:>11.2}",
+                location.name(),
+                self.throughput.get(&location).copied().unwrap_or(0),
+                self.throughput_rate(&location),
+                self.rolling_throughput_rate(&location),
+                self.max_queue(&location),
+                self.green_share(&location) * 100.0,
+                self.stops_per_car(&location)
+            ));
+        }
+        lines.join("\n")
+    }
+}
@@ -1,768 +1,1333 @@
+use crate::collision::Obb;
+use crate::conflict::{ConflictTable, Movement};
 use crate::constants::*;
+use crate::following::{CarFollowing, Obstacle};
+use crate::lane::Lane;
+use crate::layout::{Crossing, Layout};
+use crate::network::{Edge, Network, Route};
+use crate::pedestrian::{Pedestrian, WalkSignal};
+use crate::signal::{Decision, SignalTiming};
+use crate::vehicle::VehicleKind;
 use core::f64;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::f64::consts::{FRAC_PI_2, TAU};
+use std::rc::Rc;
 
 pub struct Model {
     pub cars: Vec<Car>,
+    pub pedestrians: Vec<Pedestrian>,
     pub road_marking: Vec<Line>,
-    pub traffic_light_switch: TrafficLightSwitch,
+    pub junctions: Vec<Junction>,
     pub config: Config,
+    //every random choice must come from rng so a run can be replayed from its seed
+    pub seed: u64,
+    pub rng: StdRng,
+    pub next_car_id: u64,
+    pub next_pedestrian_id: u64,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Model {
     pub fn new() -> Self {
-        let cars = vec![];
-        let road_marking = Model::create_road_markings();
-        let traffic_lights = TrafficLightSwitch::create_traffic_lights();
-        let traffic_light_switch = TrafficLightSwitch {
-            traffic_lights,
-            request: None,
-        };
+        Self::with_seed(rand::rng().random())
+    }
 
-        let config = Config {
-            small_radius: CAR_SIZE_F64 + MARGIN_F64 / 2.0,
-            big_radius: 2.0 * CAR_SIZE_F64 + 1.5 * MARGIN_F64,
-            top_left: PointF::new(
-                SCREEN_WIDTH_F64 / 2.0 - CAR_SIZE_F64 - MARGIN_F64,
-                SCREEN_HEIGHT_F64 / 2.0 - CAR_SIZE_F64 - MARGIN_F64,
-            ),
-            top_right: PointF::new(
-                SCREEN_WIDTH_F64 / 2.0 + CAR_SIZE_F64 + MARGIN_F64,
-                SCREEN_HEIGHT_F64 / 2.0 - CAR_SIZE_F64 - MARGIN_F64,
-            ),
-            bottom_left: PointF::new(
-                SCREEN_WIDTH_F64 / 2.0 - CAR_SIZE_F64 - MARGIN_F64,
-                SCREEN_HEIGHT_F64 / 2.0 + CAR_SIZE_F64 + MARGIN_F64,
-            ),
-            bottom_right: PointF::new(
-                SCREEN_WIDTH_F64 / 2.0 + CAR_SIZE_F64 + MARGIN_F64,
-                SCREEN_HEIGHT_F64 / 2.0 + CAR_SIZE_F64 + MARGIN_F64,
-            ),
-        };
+    pub fn with_seed(seed: u64) -> Self {
+        let config = Config::new(
+            CarFollowing::default(),
+            Layout::default(),
+            Network::default(),
+        );
+        Self::with_config(seed, config)
+    }
+
+    //model on the roads of config, for callers that build many models on the same roads
+    pub fn with_config(seed: u64, config: Config) -> Self {
+        let cars = vec![];
+        let road_marking = Model::create_road_markings(&config);
+        let junctions = Junction::create_junctions(&config);
 
         Self {
             cars,
+            pedestrians: vec![],
             road_marking,
-            traffic_light_switch,
+            junctions,
             config,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            next_car_id: 0,
+            next_pedestrian_id: 0,
         }
     }
 
-    pub fn spawn_car(&mut self, location: Location, destination: Destination) {
-        if !Self::is_overlap(&self.cars, &Car::calculate_initial_position(&location)) {
-            let car = Car::new(location, destination);
-            self.cars.push(car);
+    //rebuild the roads for another set of lanes or crossings, lights start over
+    pub fn set_roads(&mut self, layout: Layout, network: Network) {
+        self.config = Config::new(self.config.following.clone(), layout, network);
+        self.road_marking = Model::create_road_markings(&self.config);
+        self.junctions = Junction::create_junctions(&self.config);
+    }
+
+    //signal timing of every crossing
+    pub fn set_timing(&mut self, timing: &SignalTiming) {
+        for junction in &mut self.junctions {
+            junction.traffic_light_switch.timing = timing.clone();
         }
     }
 
-    //check if new car would spawn too close to existing car
-    pub fn is_overlap(cars: &Vec<Car>, intial_position: &PointF) -> bool {
-        let x1 = intial_position.x;
-        let y1 = intial_position.y;
-        let x2 = x1 + CAR_SIZE_F64;
-        let y2 = y1 + CAR_SIZE_F64;
+    //offsets in network order, crossings without one keep the common cycle
+    pub fn set_offsets(&mut self, offsets: &[f64]) {
+        for (junction, offset) in self.junctions.iter_mut().zip(offsets) {
+            junction.traffic_light_switch.offset = *offset;
+        }
+    }
 
-        for car in cars {
-            if car.position.x >= x1
-                && car.position.x <= x2 + SEPARATION_DISTANCE
-                && y1 == car.position.y
-            {
-                return true;
-            }
+    //car turning at the first crossing and going ahead after it
+    pub fn spawn_car(&mut self, location: Location, road: usize, destination: Destination) -> bool {
+        self.spawn_on_route(
+            &Edge {
+                side: location,
+                road,
+            },
+            Route::turn(destination),
+            VehicleKind::Car,
+        )
+    }
 
-            if x1 >= car.position.x
-                && x1 <= car.position.x + CAR_SIZE_F64 + SEPARATION_DISTANCE
-                && y1 == car.position.y
-            {
-                return true;
-            }
-            if car.position.y >= y1
-                && car.position.y <= y2 + SEPARATION_DISTANCE
-                && x1 == car.position.x
-            {
-                return true;
-            }
-            if y1 >= car.position.y
-                && y1 <= car.position.y + CAR_SIZE_F64 + SEPARATION_DISTANCE
-                && x1 == car.position.x
-            {
-                return true;
-            }
-        }
-        false
+    //vehicle takes the free lane for its first turn with the fewest vehicles waiting on it
+    pub fn spawn_on_route(&mut self, entry: &Edge, mut route: Route, kind: VehicleKind) -> bool {
+        let location = entry.side.clone();
+        let destination = route.turns.pop_front().unwrap_or(Destination::Ahead);
+        let Some(junction) = self.config.network.entry(&location, entry.road) else {
+            return false;
+        };
+        let crossing = &self.config.crossings[junction];
+        let layout = &self.config.layout;
+        let lane = layout
+            .lanes_for(&destination)
+            .into_iter()
+            .map(|index| layout.approach(crossing, &location, index))
+            .filter(|lane| !Self::is_overlap(&self.cars, lane, kind))
+            .min_by_key(|lane| {
+                self.cars
+                    .iter()
+                    .filter(|car| car.approach().is_some() && lane.covers(&car.position))
+                    .count()
+            });
+        let Some(lane) = lane else {
+            return false;
+        };
+        let mut car = Car::new(junction, lane, destination, kind, &self.config);
+        car.route = route;
+        car.id = self.next_car_id;
+        self.next_car_id += 1;
+        self.cars.push(car);
+        true
     }
 
-    //turn car at crossroads (legacy)
-    pub fn update_direction(car: &mut Car) {
-        match car.destination {
-            Destination::Left => match car.direction {
-                Location::West => {
-                    if car.position.x <= (SCREEN_WIDTH_F64 + CAR_SIZE_F64 + MARGIN_F64) / 2.0 {
-                        // car.direction = Location::South;
-                        // car.destination = Destination::Ahead;
-                        // car.deg = 90.0;
-                    }
-                }
-                Location::East => {
-                    if car.position.x >= (SCREEN_WIDTH_F64 - CAR_SIZE_F64 - MARGIN_F64) / 2.0 {
-                        // car.direction = Location::North;
-                        // car.destination = Destination::Ahead;
-                        // car.deg = 270.0;
-                    }
-                }
-                Location::North => {
-                    if car.position.y <= (SCREEN_HEIGHT_F64 + CAR_SIZE_F64 + MARGIN_F64) / 2.0 {
-                        // car.direction = Location::West;
-                        // car.destination = Destination::Ahead;
-                        // car.deg = 180.0;
-                    }
-                }
-                Location::South => {
-                    if car.position.y >= (SCREEN_HEIGHT_F64 - CAR_SIZE_F64 - MARGIN_F64) / 2.0 {
-                        // car.direction = Location::East;
-                        // car.destination = Destination::Ahead;
-                        // car.deg = 0.0;
-                    }
+    //someone waiting to cross the arm on side of crossing junction from the kerb with the larger x or y if far
+    pub fn spawn_pedestrian(&mut self, junction: usize, arm: Location, far: bool) {
+        let mut pedestrian = Pedestrian::new(junction, arm, far, &self.config);
+        pedestrian.id = self.next_pedestrian_id;
+        self.next_pedestrian_id += 1;
+        self.pedestrians.push(pedestrian);
+    }
+
+    //check if a new vehicle of kind would spawn too close to an existing one
+    pub fn is_overlap(cars: &[Car], lane: &Lane, kind: VehicleKind) -> bool {
+        cars.iter().any(|car| {
+            lane.covers(&car.position)
+                && lane.along(&car.position).abs()
+                    <= (kind.length() as f64 + car.length()) / 2.0
+                        + SEPARATION_DISTANCE
+                        + POSITION_TOLERANCE
+        })
+    }
+
+    pub fn create_road_markings(config: &Config) -> Vec<Line> {
+        let mut lines = vec![];
+        let layout = &config.layout;
+        let half_width = layout.half_width();
+        for (index, crossing) in config.crossings.iter().enumerate() {
+            let center = crossing.center.clone();
+            //kerbs round the box between arms that do not meet kerb to kerb
+            let corners = crossing.outline.len();
+            for at in (1..corners).step_by(2) {
+                let (from, to) = (&crossing.outline[at], &crossing.outline[(at + 1) % corners]);
+                if (to.x - from.x).hypot(to.y - from.y) > 1.0 {
+                    lines.push(Line {
+                        start: Point::new(from.x.round() as i32, from.y.round() as i32),
+                        end: Point::new(to.x.round() as i32, to.y.round() as i32),
+                        color: LINE_COLOR_2,
+                    });
                 }
-            },
-            Destination::Right => match car.direction {
-                Location::West => {
-                    if car.position.x <= (SCREEN_WIDTH_F64 - CAR_SIZE_F64 - MARGIN_F64) / 2.0 {
-                        // car.direction = Location::North;
-                        // car.destination = Destination::Ahead;
-                        // car.deg = 270.0;
+            }
+            for (location, _) in &crossing.arms {
+                let heading = Lane::unit(crossing.inbound(location));
+                let lane = Lane::new(location.opposite(), heading, &center);
+                //pixels along the arm to the edge of the crossing and to where the arm ends,
+                //half way to the next crossing or at the edge of the screen
+                let mut edge = lane.along(&center) - crossing.edge(location);
+                let end = match config.network.next(index, location) {
+                    Some(next) => {
+                        (lane.along(&center) + lane.along(&config.network.center(next))) / 2.0
                     }
+                    None => 0.0,
+                };
+                //zebra in front of the box, the lines start behind it
+                if layout.zebras {
+                    lines.extend(Self::zebra(&lane, edge, layout.road_half_width()));
+                    edge -= layout.zebra_width();
                 }
-                Location::East => {
-                    if car.position.x >= (SCREEN_WIDTH_F64 + CAR_SIZE_F64 + MARGIN_F64) / 2.0 {
-                        // car.direction = Location::South;
-                        // car.destination = Destination::Ahead;
-                        // car.deg = 90.0;
-                    }
+                //advanced stop line for bicycles with the box in front of the car stop line
+                if layout.bike_boxes {
+                    lines.extend(Self::bike_box(&lane, edge, layout.road_half_width()));
+                    edge -= layout.bike_box_depth();
                 }
-                Location::North => {
-                    if car.position.y <= (SCREEN_HEIGHT_F64 - CAR_SIZE_F64 - MARGIN_F64) / 2.0 {
-                        // car.direction = Location::East;
-                        // car.destination = Destination::Ahead;
-                        // car.deg = 0.0;
-                    }
+                //stop line across the lanes coming in, give way line at a roundabout
+                if layout.is_roundabout() {
+                    lines.extend(Self::give_way_line(&lane, edge));
+                } else {
+                    let start = Self::arm_point(&lane, edge, 0.0);
+                    let stop = Self::arm_point(
+                        &lane,
+                        edge,
+                        half_width + STOP_LINE_CURVE_LENGTH_ADJUSTMENT as f64,
+                    );
+                    lines.push(Line {
+                        start,
+                        end: stop,
+                        color: LINE_COLOR_2,
+                    });
                 }
-                Location::South => {
-                    if car.position.y >= (SCREEN_HEIGHT_F64 + CAR_SIZE_F64 + MARGIN_F64) / 2.0 {
-                        // car.direction = Location::West;
-                        // car.destination = Destination::Ahead;
-                        // car.deg = 180.0;
-                    }
+                //centre line
+                lines.extend(Self::dashes(&lane, edge, end, 0.0, 50.0, 25.0));
+                //lanes coming in and going out
+                for index in 1..layout.lanes.len() {
+                    let aside = index as f64 * LANE_WIDTH;
+                    lines.extend(Self::dashes(&lane, edge, end, aside, 25.0, 25.0));
+                    lines.extend(Self::dashes(&lane, edge, end, -aside, 25.0, 25.0));
                 }
-            },
-            _ => {}
+            }
+            //outer edge of the ring and the island inside it
+            if layout.is_roundabout() {
+                lines.extend(Self::circle(&center, half_width));
+                lines.extend(Self::circle(&center, ROUNDABOUT_RADIUS - LANE_WIDTH / 2.0));
+            }
         }
+        lines
     }
 
-    pub fn create_road_markings() -> Vec<Line> {
+    //stripes along the arm over the whole road from edge back
+    fn zebra(lane: &Lane, edge: f64, half_width: f64) -> Vec<Line> {
         let mut lines = vec![];
-        //Break Point East
-        let start = Point::new(BREAK_POINT_EAST as i32, SCREEN_HEIGHT / 2);
-        let end = Point::new(
-            BREAK_POINT_EAST as i32,
-            SCREEN_HEIGHT / 2 + CAR_SIZE + MARGIN + STOP_LINE_CURVE_LENGTH_ADJUSTMENT,
-        );
-        let line = Line {
-            start,
-            end,
-            color: LINE_COLOR_2,
-        };
-        lines.push(line);
-        //Break Point West
-        let start = Point::new(
-            BREAK_POINT_WEST as i32,
-            SCREEN_HEIGHT / 2 - CAR_SIZE - MARGIN - STOP_LINE_CURVE_LENGTH_ADJUSTMENT,
-        );
-        let end = Point::new(BREAK_POINT_WEST as i32, SCREEN_HEIGHT / 2);
-        let line = Line {
-            start,
-            end,
-            color: LINE_COLOR_2,
-        };
-        lines.push(line);
-        //Break Point North
-        let start = Point::new(
-            SCREEN_WIDTH / 2 + CAR_SIZE + MARGIN + STOP_LINE_CURVE_LENGTH_ADJUSTMENT,
-            BREAK_POINT_NORTH as i32,
-        );
-        let end = Point::new(SCREEN_WIDTH / 2, BREAK_POINT_NORTH as i32);
-        let line = Line {
-            start,
-            end,
-            color: LINE_COLOR_2,
-        };
-        lines.push(line);
-        //Break Point South
-        let start = Point::new(
-            SCREEN_WIDTH / 2 - CAR_SIZE - MARGIN - STOP_LINE_CURVE_LENGTH_ADJUSTMENT,
-            BREAK_POINT_SOUTH as i32,
-        );
-        let end = Point::new(SCREEN_WIDTH / 2, BREAK_POINT_SOUTH as i32);
-        let line = Line {
-            start,
-            end,
-            color: LINE_COLOR_2,
-        };
-        lines.push(line);
-        //Additional markings
-        let mut gap = 0;
-        for i in 0..10 {
-            if i % 2 != 0 {
-                gap += 25;
-                continue;
+        let mut aside = -half_width + 2.0;
+        while aside < half_width {
+            //every stripe is a few lines wide
+            for line in 0..4 {
+                let aside = aside + line as f64;
+                lines.push(Line {
+                    start: Self::arm_point(lane, edge, aside),
+                    end: Self::arm_point(lane, edge - ZEBRA_WIDTH, aside),
+                    color: ZEBRA_COLOR,
+                });
             }
-            let start = Point::new(BREAK_POINT_WEST as i32 - gap, SCREEN_HEIGHT / 2);
-            let end = Point::new(BREAK_POINT_WEST as i32 - 50 - gap, SCREEN_HEIGHT / 2);
-            let line = Line {
-                start,
-                end,
-                color: LINE_COLOR_2,
-            };
-            lines.push(line);
-            gap += 50;
+            aside += 8.0;
         }
-        let mut gap = 0;
-        for i in 0..10 {
-            if i % 2 != 0 {
-                gap += 25;
-                continue;
-            }
-            let start = Point::new(BREAK_POINT_EAST as i32 + gap, SCREEN_HEIGHT / 2);
-            let end = Point::new(BREAK_POINT_EAST as i32 + 50 + gap, SCREEN_HEIGHT / 2);
-            let line = Line {
-                start,
-                end,
+        lines
+    }
+
+    //advanced stop line across the lanes coming in at edge and the sides of the box behind it
+    fn bike_box(lane: &Lane, edge: f64, half_width: f64) -> Vec<Line> {
+        let back = edge - BIKE_BOX_DEPTH;
+        [
+            (edge, 0.0, edge, half_width),
+            (edge, 0.0, back, 0.0),
+            (edge, half_width, back, half_width),
+        ]
+        .iter()
+        .map(|(from, from_aside, to, to_aside)| Line {
+            start: Self::arm_point(lane, *from, *from_aside),
+            end: Self::arm_point(lane, *to, *to_aside),
+            color: BIKE_BOX_COLOR,
+        })
+        .collect()
+    }
+
+    //short dashes across the lane coming in at the edge of the roundabout
+    fn give_way_line(lane: &Lane, edge: f64) -> Vec<Line> {
+        let mut lines = vec![];
+        let mut aside = 0.0;
+        while aside < LANE_WIDTH {
+            lines.push(Line {
+                start: Self::arm_point(lane, edge, aside),
+                end: Self::arm_point(lane, edge, (aside + 4.0).min(LANE_WIDTH)),
                 color: LINE_COLOR_2,
-            };
-            lines.push(line);
-            gap += 50;
+            });
+            aside += 8.0;
         }
-        let mut gap = 0;
-        for i in 0..10 {
-            if i % 2 != 0 {
-                gap += 25;
-                continue;
-            }
-            let start = Point::new(SCREEN_WIDTH / 2, BREAK_POINT_NORTH as i32 - gap);
-            let end = Point::new(SCREEN_WIDTH / 2, BREAK_POINT_NORTH as i32 - 50 - gap);
-            let line = Line {
-                start,
-                end,
+        lines
+    }
+
+    //circle round center drawn as short straight lines
+    fn circle(center: &PointF, radius: f64) -> Vec<Line> {
+        let segments = 48;
+        let point = |index: usize| {
+            let angle = index as f64 * TAU / segments as f64;
+            Point::new(
+                (center.x + radius * angle.cos()).round() as i32,
+                (center.y + radius * angle.sin()).round() as i32,
+            )
+        };
+        (0..segments)
+            .map(|index| Line {
+                start: point(index),
+                end: point(index + 1),
                 color: LINE_COLOR_2,
-            };
-            lines.push(line);
-            gap += 50;
-        }
-        let mut gap = 0;
-        for i in 0..10 {
-            if i % 2 != 0 {
-                gap += 25;
-                continue;
-            }
-            let start = Point::new(SCREEN_WIDTH / 2, BREAK_POINT_SOUTH as i32 + gap);
-            let end = Point::new(SCREEN_WIDTH / 2, BREAK_POINT_SOUTH as i32 + 50 + gap);
-            let line = Line {
-                start,
-                end,
+            })
+            .collect()
+    }
+
+    //point along the centre line lane and aside pixels to the left of it
+    fn arm_point(lane: &Lane, along: f64, aside: f64) -> Point {
+        let point = lane.point(along);
+        let (dx, dy) = lane.heading;
+        Point::new(
+            (point.x + dy * aside).round() as i32,
+            (point.y - dx * aside).round() as i32,
+        )
+    }
+
+    //dashed line from the edge of the crossing back to the end of its arm
+    fn dashes(lane: &Lane, edge: f64, end: f64, aside: f64, dash: f64, gap: f64) -> Vec<Line> {
+        let mut lines = vec![];
+        let mut along = edge;
+        while along > end {
+            lines.push(Line {
+                start: Self::arm_point(lane, along, aside),
+                end: Self::arm_point(lane, (along - dash).max(end), aside),
                 color: LINE_COLOR_2,
-            };
-            lines.push(line);
-            gap += 50;
+            });
+            along -= dash + gap;
         }
         lines
     }
 
-    pub fn is_crossing_clear(cars: Vec<Car>) -> bool {
-        let p1 = PointF::new(
-            SCREEN_WIDTH_F64 / 2.0 - CAR_SIZE_F64 - MARGIN_F64,
-            SCREEN_HEIGHT_F64 / 2.0 - CAR_SIZE_F64 - MARGIN_F64,
-        );
-        let p2 = PointF::new(
-            SCREEN_WIDTH_F64 / 2.0 + CAR_SIZE_F64 + MARGIN_F64,
-            SCREEN_HEIGHT_F64 / 2.0 + CAR_SIZE_F64 + MARGIN_F64,
-        );
-
-        for car in &cars {
-            let tl = PointF::new(
-                car.position.x - CAR_SIZE_F64 / 2.0,
-                car.position.y - CAR_SIZE_F64 / 2.0,
-            );
-            let tr = PointF::new(
-                car.position.x + CAR_SIZE_F64 / 2.0,
-                car.position.y - CAR_SIZE_F64 / 2.0,
-            );
-            let bl = PointF::new(
-                car.position.x - CAR_SIZE_F64 / 2.0,
-                car.position.y + CAR_SIZE_F64 / 2.0,
-            );
-            let br = PointF::new(
-                car.position.x + CAR_SIZE_F64 / 2.0,
-                car.position.y + CAR_SIZE_F64 / 2.0,
-            );
-            if tl.x > p1.x && tl.x < p2.x && tl.y > p1.y && tl.y < p2.y {
-                return false;
-            }
-            if bl.x > p1.x && bl.x < p2.x && bl.y > p1.y && bl.y < p2.y {
-                return false;
-            }
-            if tr.x > p1.x && tr.x < p2.x && tr.y > p1.y && tr.y < p2.y {
-                return false;
-            }
-            if br.x > p1.x && br.x < p2.x && br.y > p1.y && br.y < p2.y {
-                return false;
-            }
-        }
-        true
+    //no car inside the box of crossing index
+    pub fn is_crossing_clear(cars: &[Car], index: usize, config: &Config) -> bool {
+        !cars.iter().any(|car| {
+            car.junction == Some(index) && (car.is_in_crossing(config) || car.is_entering(config))
+        })
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Car {
     pub position: PointF,
+    pub kind: VehicleKind,
     pub size: Dimen,
     pub color: ColorOrUrl,
     pub destination: Destination,
     pub direction: Location,
     pub deg: f64,
     pub rad: f64,
+    //ticks spent standing still
+    pub waiting: u64,
+    pub is_stopped: bool,
+    pub id: u64,
+    //where the car came into its crossing from and the turn it takes there
+    pub origin: Location,
+    pub turn: Destination,
+    //arm the car leaves its crossing on and the degrees it turns through to get there,
+    //clockwise on screen, none once it has
+    pub exit: Location,
+    pub bend: f64,
+    //side of the screen the car came in from
+    pub entry: Location,
+    //crossing the car is heading for or crossing, none once it has left the last one
+    pub junction: Option<usize>,
+    //ticks since spawn
+    pub age: u64,
+    //ticks standing before the stop line
+    pub queue_ticks: u64,
+    //ticks inside the crossing box
+    pub crossing_ticks: u64,
+    //times the car came to a standstill
+    pub stops: u64,
+    //pixels per tick
+    pub speed: f64,
+    //pixels driven since spawn
+    pub distance: f64,
+    //lane the car comes into its crossing on
+    pub lane: Lane,
+    //pixels along its lane where the car waits at the stop line
+    pub stop_line: f64,
+    //pixels along its lane where the car starts its arc, past the stop line for sharp far side turns
+    pub turn_start: f64,
+    //turns at the crossings after this one
+    pub route: Route,
+    //where the car is on the ring of a roundabout, none off the ring
+    pub ring: Option<Circling>,
 }
 impl Car {
-    pub fn new(location: Location, destination: Destination) -> Self {
-        let position = Car::calculate_initial_position(&location);
-        let dimen = Dimen::new(CAR_SIZE, CAR_SIZE);
-        let color_or_url = match destination {
-            Destination::Ahead => ColorOrUrl {
-                color: CAR_COLOR_WHITE,
-                url: String::from(WHITE_CAR_URL),
-            },
-            Destination::Right => ColorOrUrl {
-                color: CAR_COLOR_ORANGE,
-                url: String::from(ORANGE_CAR_URL),
-            },
-            Destination::Left => ColorOrUrl {
-                color: CAR_COLOR_BLUE,
-                url: String::from(BLUE_CAR_URL),
-            },
-        };
-        let direction = match location {
-            Location::East => Location::West,
-            Location::West => Location::East,
-            Location::North => Location::South,
-            Location::South => Location::North,
-        };
-
-        let deg = match location {
-            Location::East => 180.0,
-            Location::West => 0.0,
-            Location::North => 90.0,
-            Location::South => 270.0,
-        };
-
-        let rad = match location {
-            Location::East => 180.0_f64.to_radians(),
-            Location::West => 0.0_f64.to_radians(),
-            Location::North => 90.0_f64.to_radians(),
-            Location::South => 270.0_f64.to_radians(),
-        };
+    pub fn new(
+        junction: usize,
+        lane: Lane,
+        destination: Destination,
+        kind: VehicleKind,
+        config: &Config,
+    ) -> Self {
+        let position = lane.point(0.0);
+        let location = lane.direction.opposite();
+        let crossing = &config.crossings[junction];
+        let exit = config
+            .network
+            .turned(junction, &lane.direction, &destination)
+            .unwrap_or_else(|| lane.direction.turned(&destination));
+        let stop_line = config.layout.stop_line(crossing, &lane, kind, &exit);
+        let turn_start = config.layout.turn_start(crossing, &lane, kind, &exit);
+        let dimen = Dimen::new(kind.width(), kind.length());
+        let color_or_url = ColorOrUrl::of(kind, &destination);
+        let direction = location.opposite();
+        let bend = crossing.bend(&location, &exit);
+        let deg = crossing.inbound(&location);
+        let rad = deg.to_radians();
 
         Self {
             position,
+            kind,
             size: dimen,
             color: color_or_url,
-            destination,
+            destination: destination.clone(),
             direction,
             deg,
             rad,
+            waiting: 0,
+            is_stopped: false,
+            id: 0,
+            entry: location.clone(),
+            origin: location,
+            turn: destination,
+            exit,
+            bend,
+            junction: Some(junction),
+            age: 0,
+            queue_ticks: 0,
+            crossing_ticks: 0,
+            stops: 0,
+            //vehicles enter the screen at the speed they would drive on an empty road
+            speed: config.following.free_speed(kind),
+            distance: 0.0,
+            lane,
+            stop_line,
+            turn_start,
+            route: Route::default(),
+            ring: None,
         }
     }
-    pub fn calculate_initial_position(location: &Location) -> PointF {
-        let position = match location {
-            Location::West => {
-                PointF::new(0.0, (SCREEN_HEIGHT_F64 - MARGIN_F64 - CAR_SIZE_F64) / 2.0)
-            }
-            Location::North => {
-                PointF::new((SCREEN_WIDTH_F64 + MARGIN_F64 + CAR_SIZE_F64) / 2.0, 0.0)
-            }
-            Location::East => PointF::new(
-                SCREEN_WIDTH_F64,
-                (SCREEN_HEIGHT_F64 + MARGIN_F64 + CAR_SIZE_F64) / 2.0,
-            ),
-            Location::South => PointF::new(
-                (SCREEN_WIDTH_F64 - MARGIN_F64 - CAR_SIZE_F64) / 2.0,
-                SCREEN_HEIGHT_F64,
-            ),
+
+    pub fn waiting_time(&self) -> f64 {
+        self.waiting as f64 * TICK_DURATION
+    }
+
+    //pixels from front to back
+    pub fn length(&self) -> f64 {
+        self.size.length as f64
+    }
+
+    //unit vector the car drives along, that of its lane while it turns or circles
+    pub fn heading(&self) -> (f64, f64) {
+        if self.ring.is_some() || self.is_turning() {
+            self.lane.heading
+        } else {
+            Lane::unit(self.deg)
+        }
+    }
+
+    //any corner of the car inside the box of its crossing
+    pub fn is_in_crossing(&self, config: &Config) -> bool {
+        let Some(junction) = self.junction else {
+            return false;
+        };
+        //half of the car lengthwise in the direction it drives and across it
+        let (dx, dy) = self.heading();
+        let (half_length, half_width) = (self.length() / 2.0, self.size.width as f64 / 2.0);
+        [(1.0, 1.0), (1.0, -1.0), (-1.0, 1.0), (-1.0, -1.0)]
+            .iter()
+            .map(|(forward, side)| {
+                let (forward, side) = (forward * half_length, side * half_width);
+                PointF::new(
+                    self.position.x + forward * dx - side * dy,
+                    self.position.y + forward * dy + side * dx,
+                )
+            })
+            .any(|p| config.crossings[junction].is_inside(&p))
+    }
+
+    //past the stop line on the zebra in front of the box or on the stretch before it
+    //a long vehicle keeps to swing round, not inside it yet
+    pub fn is_entering(&self, config: &Config) -> bool {
+        let Some(junction) = self.junction else {
+            return false;
         };
+        let crossing = &config.crossings[junction];
+        let edge =
+            self.lane.along(&crossing.center) - crossing.edge(&self.origin) - self.length() / 2.0;
+        self.ring.is_none()
+            && self.stop_line_distance().is_some_and(|distance| {
+                distance <= -POSITION_TOLERANCE
+                    && distance > self.stop_line - edge - POSITION_TOLERANCE
+            })
+    }
 
-        position
+    pub fn is_off_screen(&self) -> bool {
+        let (dx, dy) = self.heading();
+        (dx > 0.0 && self.position.x > SCREEN_WIDTH_F64)
+            || (dx < 0.0 && self.position.x < -self.length())
+            || (dy > 0.0 && self.position.y > SCREEN_HEIGHT_F64)
+            || (dy < 0.0 && self.position.y < -self.length())
+    }
+
+    //approach the car is on while it has not passed the stop line yet
+    pub fn approach(&self) -> Option<Location> {
+        self.stop_line_distance()
+            .filter(|distance| *distance >= 0.0)
+            .map(|_| self.origin.clone())
     }
     pub fn drive(
         &mut self,
-        cars: &Vec<Car>,
-        traffic_lights: &HashMap<Location, TrafficLight>,
+        cars: &[Car],
+        pedestrians: &[Pedestrian],
+        junctions: &[Junction],
         config: &Config,
     ) {
-        //check separation distance
-        //West Side
-        if self.direction == Location::East {
-            let max_x = self.position.x + CAR_SIZE_F64 + SEPARATION_DISTANCE;
-            for c in cars {
-                if c.direction == Location::East && c.position.x == max_x {
-                    return;
+        if config.layout.is_roundabout() {
+            self.drive_roundabout(cars, pedestrians, junctions, config);
+            return;
+        }
+        let mut obstacles = self.pedestrian_obstacles(pedestrians, junctions, config);
+
+        //check separation distance along the lane
+        let lane = Lane::of(self);
+        let along = lane.along(&self.position);
+        let turning = self.is_turning();
+        for c in cars {
+            if c.id == self.id {
+                continue;
+            }
+            //a car ahead on the same arc has turned away from the straight lane
+            let same_turn = c.is_turning()
+                && c.direction == self.direction
+                && c.junction == self.junction
+                && c.destination == self.destination
+                && c.lane.beside(&self.lane).abs() < POSITION_TOLERANCE;
+            let ahead = if same_turn {
+                c.progress(config) - self.progress(config)
+            } else if c.direction == self.direction && lane.covers(&c.position) {
+                lane.along(&c.position) - along
+            } else if !turning {
+                //the tail of a long vehicle on its arc or a car crossing the lane
+                //blocks it with part of its box
+                let half_width = self.size.width as f64 / 2.0;
+                let Some(nearest) = Obb::of(c).nearest_along(&lane, half_width) else {
+                    continue;
+                };
+                if nearest > along + POSITION_TOLERANCE {
+                    let (dx, dy) = self.heading();
+                    let (sin, cos) = c.rad.sin_cos();
+                    obstacles.push(Obstacle::Car {
+                        gap: nearest - along - self.length() / 2.0,
+                        speed: (c.speed * (cos * dx + sin * dy)).max(0.0),
+                    });
                 }
+                continue;
+            } else {
+                continue;
+            };
+            if ahead > POSITION_TOLERANCE {
+                obstacles.push(Obstacle::Car {
+                    gap: ahead - (self.length() + c.length()) / 2.0,
+                    speed: c.speed,
+                });
             }
         }
-        //East Side
-        if self.direction == Location::West {
-            let max_x = self.position.x - CAR_SIZE_F64 - SEPARATION_DISTANCE;
+
+        //keep the distance to cars on the exit lane while turning, a queue may reach back to the box
+        if let (true, Some(junction)) = (self.is_turning(), self.junction) {
+            let crossing = &config.crossings[junction];
+            let radius = config
+                .layout
+                .turn_radius(crossing, &self.lane, self.kind, &self.exit);
+            let left =
+                radius * self.bend.abs().to_radians() - (self.progress(config) - self.turn_start);
+            let exit = self.exit.clone();
+            let end = self.turn_end(crossing, config);
+            let exit_lane = Lane::new(exit.clone(), crossing.heading(&exit), &end);
             for c in cars {
-                if c.direction == Location::West && c.position.x == max_x {
-                    return;
+                if c.id == self.id || c.direction != exit || !exit_lane.covers(&c.position) {
+                    continue;
+                }
+                let beyond = exit_lane.along(&c.position) - exit_lane.along(&end);
+                if beyond > -POSITION_TOLERANCE {
+                    obstacles.push(Obstacle::Car {
+                        gap: left + beyond - (self.length() + c.length()) / 2.0,
+                        speed: c.speed,
+                    });
+                    continue;
+                }
+                //long arcs end well past the box, a car that has just left the crossing
+                //short of the end is in the way once it is ahead of the front of the car,
+                //cars still coming towards the crossing on that lane are someone else's business
+                let (sin, cos) = self.rad.sin_cos();
+                let half = self.length() / 2.0;
+                let front = PointF::new(self.position.x + half * cos, self.position.y + half * sin);
+                let ahead = exit_lane.along(&c.position) - exit_lane.along(&front);
+                if ahead > POSITION_TOLERANCE {
+                    obstacles.push(Obstacle::Car {
+                        gap: ahead - c.length() / 2.0,
+                        speed: c.speed,
+                    });
                 }
             }
         }
-        //North Side
-        if self.direction == Location::South {
-            let max_y = self.position.y + CAR_SIZE_F64 + SEPARATION_DISTANCE;
-            for c in cars {
-                if c.direction == Location::South && c.position.y == max_y {
-                    return;
+
+        //check traffic light, right of way and room behind the crossing,
+        //hold while an emergency vehicle calls so it does not meet anyone going on amber
+        if let (Some(distance), Some(junction)) = (self.stop_line_distance(), self.junction) {
+            let traffic_lights = &junctions[junction].traffic_light_switch.traffic_lights;
+            //a car braking onto the line may end up a rounding error beyond it
+            if distance > -POSITION_TOLERANCE
+                && (!traffic_lights[&self.origin].allows_entry(self)
+                    || self.must_yield(cars, traffic_lights, config)
+                    || self.is_exit_blocked(cars, config)
+                    || self.holds_for_emergency(cars))
+            {
+                obstacles.push(Obstacle::StopLine {
+                    distance: self.hold_distance(distance, config),
+                });
+            }
+        }
+
+        self.speed = config
+            .following
+            .next_speed(self.speed, self.kind, &obstacles);
+        if self.speed <= 0.0 {
+            return;
+        }
+
+        //check turning position, turns start at the stop line or a little past it
+        let distance = self.turn_distance();
+        if self.is_turning() {
+            self.distance += self.speed;
+            self.turn_step(config);
+            return;
+        }
+
+        //stop at the start of the turn instead of running past it
+        let step = match distance {
+            Some(distance) if self.bend != 0.0 => self.speed.min(distance),
+            _ => self.speed,
+        };
+        self.distance += step;
+        self.step_ahead(step);
+    }
+
+    //follow the car ahead along the lane or round the ring and give way to the ring before joining it
+    fn drive_roundabout(
+        &mut self,
+        cars: &[Car],
+        pedestrians: &[Pedestrian],
+        junctions: &[Junction],
+        config: &Config,
+    ) {
+        let mut obstacles = self.pedestrian_obstacles(pedestrians, junctions, config);
+        let lane = Lane::of(self);
+        let along = lane.along(&self.position);
+        let ring = self.ring_position(config);
+        for c in cars {
+            if c.id == self.id {
+                continue;
+            }
+            let other = c.ring_position(config).or_else(|| c.leaving_ring(config));
+            let ahead = match (&ring, other) {
+                //cars further round than where the car leaves the ring are out of its way,
+                //the ends of long vehicles swing wide of the ring where they join and leave it
+                (Some((angle, left)), Some((other, _))) if c.junction == self.junction => {
+                    let ahead = (other - angle).rem_euclid(TAU) * ROUNDABOUT_RADIUS;
+                    let swing = (self.length().max(c.length()) - CAR_SIZE_F64).max(0.0);
+                    if ahead >= left + (self.length() + c.length()) / 2.0 + swing {
+                        continue;
+                    }
+                    ahead
+                }
+                (None, _)
+                    if c.ring.is_none()
+                        && c.direction == self.direction
+                        && lane.covers(&c.position) =>
+                {
+                    lane.along(&c.position) - along
                 }
+                _ => continue,
+            };
+            if ahead > POSITION_TOLERANCE {
+                obstacles.push(Obstacle::Car {
+                    gap: ahead - (self.length() + c.length()) / 2.0,
+                    speed: c.speed,
+                });
             }
         }
-        //South Side
-        if self.direction == Location::North {
-            let max_y = self.position.y - CAR_SIZE_F64 - SEPARATION_DISTANCE;
+
+        if let (Some((_, left)), Some(junction)) = (&ring, self.junction) {
+            //keep the distance to cars on the lane out of the ring, a queue may reach back to it
+            let crossing = &config.crossings[junction];
+            let exit = config.layout.departure(crossing, &self.ring_exit(), 0);
+            let leave = config.layout.ring_leave(&crossing.center, &exit);
             for c in cars {
-                if c.direction == Location::North && c.position.y == max_y {
-                    return;
+                if c.id == self.id || c.direction != exit.direction || !exit.covers(&c.position) {
+                    continue;
+                }
+                let beyond = exit.along(&c.position) - leave;
+                if beyond > -POSITION_TOLERANCE {
+                    obstacles.push(Obstacle::Car {
+                        gap: left + beyond - (self.length() + c.length()) / 2.0,
+                        speed: c.speed,
+                    });
                 }
             }
+        } else if let (Some(distance), Some(junction)) = (self.stop_line_distance(), self.junction)
+        {
+            //give way to the ring and keep out if the road behind it is full,
+            //hold while an emergency vehicle comes so the ring clears for it
+            if distance > -POSITION_TOLERANCE
+                && (self.must_give_way(cars, &config.crossings[junction], config)
+                    || self.is_exit_blocked(cars, config)
+                    || self.holds_for_emergency(cars))
+            {
+                obstacles.push(Obstacle::StopLine { distance });
+            }
         }
 
-        //check traffic light
-        //West Side
-        if self.position.x == SCREEN_WIDTH_F64 / 2.0 - CAR_SIZE_F64 * 1.5 - MARGIN_F64
-            && self.direction == Location::East
-            && traffic_lights[&Location::West].status == false
-        {
+        self.speed = config
+            .following
+            .next_speed(self.speed, self.kind, &obstacles);
+        if self.speed <= 0.0 {
             return;
         }
-        //East Side
-        if self.position.x == SCREEN_WIDTH_F64 / 2.0 + CAR_SIZE_F64 * 1.5 + MARGIN_F64
-            && self.direction == Location::West
-            && traffic_lights[&Location::East].status == false
-        {
+        self.distance += self.speed;
+        let Some(junction) = self.junction else {
+            self.step_ahead(self.speed);
             return;
+        };
+        let crossing = &config.crossings[junction];
+        let center = crossing.center.clone();
+        match self.ring.clone() {
+            Some(mut circling) => {
+                circling.angle += self.speed / ROUNDABOUT_RADIUS;
+                let (from, sweep) = self.ring_span(crossing, config);
+                if circling.angle < sweep {
+                    self.place_on_ring(&center, from + circling.angle);
+                    self.ring = Some(circling);
+                    return;
+                }
+                //out onto the lane of the exit
+                let exit = self.ring_exit();
+                let lane = config.layout.departure(crossing, &exit, 0);
+                let leave = config.layout.ring_leave(&center, &lane);
+                self.position = lane.point(leave + (circling.angle - sweep) * ROUNDABOUT_RADIUS);
+                self.deg = crossing.angle(&exit);
+                self.direction = exit;
+                self.destination = Destination::Ahead;
+                self.bend = 0.0;
+                self.rad = self.deg.to_radians();
+                self.ring = None;
+            }
+            None => {
+                let join = config.layout.ring_join(&center, &self.lane);
+                let joins = self.direction == self.origin.opposite()
+                    && along < join
+                    && along + self.speed >= join;
+                if !joins {
+                    self.step_ahead(self.speed);
+                    return;
+                }
+                let circling = Circling {
+                    joined: join,
+                    angle: (along + self.speed - join) / ROUNDABOUT_RADIUS,
+                };
+                let (from, _) = self.ring_span(crossing, config);
+                self.place_on_ring(&center, from + circling.angle);
+                self.ring = Some(circling);
+            }
         }
-        //South side
-        if self.position.y == SCREEN_HEIGHT_F64 / 2.0 + CAR_SIZE_F64 * 1.5 + MARGIN_F64
-            && self.direction == Location::North
-            && traffic_lights[&Location::South].status == false
-        {
-            return;
+    }
+
+    //stop before a zebra with someone walking across the car's lane on it,
+    //at a dark zebra also for people waiting at the kerb while the car can still stop comfortably
+    fn pedestrian_obstacles(
+        &self,
+        pedestrians: &[Pedestrian],
+        junctions: &[Junction],
+        config: &Config,
+    ) -> Vec<Obstacle> {
+        let mut obstacles = vec![];
+        let Some(junction) = self.junction else {
+            return obstacles;
+        };
+        let walk = &junctions[junction].traffic_light_switch.walk;
+        for pedestrian in pedestrians.iter().filter(|p| p.junction == junction) {
+            let Some((distance, lane)) = self.to_zebra(&pedestrian.arm, config) else {
+                continue;
+            };
+            if distance <= -POSITION_TOLERANCE || !pedestrian.blocks(&lane, config) {
+                continue;
+            }
+            let dark = walk.get(&pedestrian.arm) == Some(&WalkSignal::Dark);
+            if pedestrian.walking || (dark && self.braking_distance() <= distance) {
+                obstacles.push(Obstacle::StopLine { distance });
+            }
         }
-        //North side
-        if self.position.y == SCREEN_HEIGHT_F64 / 2.0 - CAR_SIZE_F64 * 1.5 - MARGIN_F64
-            && self.direction == Location::South
-            && traffic_lights[&Location::North].status == false
-        {
-            return;
+        obstacles
+    }
+
+    //pixels from the front of the car to the zebra on side of its crossing and the lane it crosses it on,
+    //none if it does not cross that zebra, negative while on it,
+    //a car waits for the zebra behind the crossing at its stop line
+    pub fn to_zebra(&self, side: &Location, config: &Config) -> Option<(f64, Lane)> {
+        let crossing = &config.crossings[self.junction?];
+        let distance = self.stop_line_distance();
+        if *side == self.origin && self.ring.is_none() {
+            return distance.map(|distance| (distance, self.lane.clone()));
+        }
+        let exit = self.ring_exit();
+        if *side != exit {
+            return None;
+        }
+        let lane = if self.direction == exit && self.bend == 0.0 {
+            Lane::of(self)
+        } else if config.layout.is_roundabout() {
+            config.layout.departure(crossing, &exit, 0)
+        } else {
+            self.exit_lane(crossing, config)
+        };
+        if let Some(distance) = distance.filter(|distance| *distance > -POSITION_TOLERANCE) {
+            return Some((distance, lane));
         }
+        //a car halfway round a turn reaches the zebra with a corner first
+        let (sin, cos) = self.rad.sin_cos();
+        let half_length = self.length() / 2.0;
+        let half_width = self.size.width as f64 / 2.0;
+        let front = [(1.0, 1.0), (1.0, -1.0), (-1.0, 1.0), (-1.0, -1.0)]
+            .iter()
+            .map(|(forward, side)| {
+                let (forward, side) = (forward * half_length, side * half_width);
+                let x = self.position.x + forward * cos - side * sin;
+                let y = self.position.y + forward * sin + side * cos;
+                lane.along(&PointF::new(x, y))
+            })
+            .fold(f64::NEG_INFINITY, f64::max);
+        let near = lane.along(&crossing.center) + crossing.edge(&exit);
+        Some((near - front, lane))
+    }
 
-        //Model::update_direction(self);
-
-        //check turning position
-        match self.direction {
-            Location::East => {
-                match self.destination {
-                    Destination::Left => {
-                        if self.position.x + CAR_SIZE_F64 / 2.0 >= config.top_left.x {
-                            //small radius
-                            let r = config.small_radius;
-                            let rad = CAR_SPEED as f64 / r;
-                            let deg = rad.to_degrees();
-                            self.deg -= deg;
-                            self.rad -= rad;
-
-                            if self.deg.abs() >= 90.0 {
-                                self.direction = Location::North;
-                                self.destination = Destination::Ahead;
-                                self.deg = 270.0;
-                                self.rad = self.deg.to_radians();
-                                return;
-                            }
-                            let dx = r * self.rad.abs().sin();
-
-                            let x = SCREEN_WIDTH_F64 / 2.0
-                                - CAR_SIZE_F64
-                                - MARGIN_F64
-                                - CAR_SIZE_F64 / 2.0
-                                + dx;
-
-                            let dy = r - (self.rad.abs().cos()) * r;
-                            let y = SCREEN_HEIGHT_F64 / 2.0
-                                - MARGIN_F64 / 2.0
-                                - CAR_SIZE_F64 / 2.0
-                                - dy;
-                            self.position.x = x;
-                            self.position.y = y;
-                            return;
-                        }
-                    }
-                    Destination::Right => {
-                        if self.position.x + CAR_SIZE_F64 / 2.0 >= config.top_left.x {
-                            //big radius
-                            let r = config.big_radius;
-                            let rad = CAR_SPEED as f64 / r;
-                            let deg = rad.to_degrees();
-                            self.deg += deg;
-                            self.rad += rad;
-                            if self.deg.abs() >= 90.0 {
-                                self.direction = Location::South;
-                                self.destination = Destination::Ahead;
-                                self.deg = 90.0;
-                                self.rad = self.deg.to_radians();
-                                return;
-                            }
-                            let dx = r * self.rad.sin();
-                            let x = SCREEN_WIDTH_F64 / 2.0
-                                - CAR_SIZE_F64
-                                - MARGIN_F64
-                                - CAR_SIZE_F64 / 2.0
-                                + dx;
-                            let dy = r - (self.rad.cos()) * r;
-                            let y = SCREEN_HEIGHT_F64 / 2.0 - MARGIN_F64 / 2.0 - CAR_SIZE_F64 / 2.0
-                                + dy;
-                            self.position.x = x;
-                            self.position.y = y;
-                            return;
-                        }
-                    }
-                    _ => {}
+    //on the zebra pedestrian wants to cross or too close to stop before it, in the way on the lane
+    pub fn is_committed_to(&self, pedestrian: &Pedestrian, config: &Config) -> bool {
+        if self.junction != Some(pedestrian.junction) {
+            return false;
+        }
+        let Some((distance, lane)) = self.to_zebra(&pedestrian.arm, config) else {
+            return false;
+        };
+        pedestrian.blocks(&lane, config)
+            && distance > -(config.layout.zebra_width() + self.length())
+            && distance < self.braking_distance() + self.speed
+    }
+
+    fn step_ahead(&mut self, step: f64) {
+        let (dx, dy) = self.heading();
+        self.position.x += dx * step;
+        self.position.y += dy * step;
+    }
+
+    //where the car leaves the ring heading to
+    fn ring_exit(&self) -> Location {
+        self.exit.clone()
+    }
+
+    //radians round the ring of the roundabout of crossing where the car joins it
+    //and from there to where it leaves
+    fn ring_span(&self, crossing: &Crossing, config: &Config) -> (f64, f64) {
+        let layout = &config.layout;
+        let center = &crossing.center;
+        let from = Layout::ring_angle(
+            center,
+            &self.lane.point(layout.ring_join(center, &self.lane)),
+        );
+        let exit = layout.departure(crossing, &self.ring_exit(), 0);
+        let to = Layout::ring_angle(center, &exit.point(layout.ring_leave(center, &exit)));
+        (from, (to - from).rem_euclid(TAU))
+    }
+
+    fn place_on_ring(&mut self, center: &PointF, angle: f64) {
+        self.position = Layout::ring_point(center, angle);
+        //driving clockwise the car points a quarter turn on from the centre
+        self.rad = (angle + FRAC_PI_2).rem_euclid(TAU);
+        self.deg = self.rad.to_degrees();
+    }
+
+    //radians round the ring of the car and pixels left to where it leaves it,
+    //a car past the give way line counts from where it is about to join
+    fn ring_position(&self, config: &Config) -> Option<(f64, f64)> {
+        let crossing = &config.crossings[self.junction?];
+        let center = &crossing.center;
+        let (from, sweep) = self.ring_span(crossing, config);
+        let angle = match &self.ring {
+            Some(circling) => circling.angle,
+            None => {
+                let distance = self.stop_line_distance()?;
+                let along = self.lane.along(&self.position);
+                if distance > -POSITION_TOLERANCE
+                    || along >= config.layout.ring_join(center, &self.lane)
+                {
+                    return None;
                 }
+                0.0
             }
-            Location::West => {
-                match self.destination {
-                    Destination::Left => {
-                        if self.position.x - CAR_SIZE_F64 / 2.0 <= config.top_right.x {
-                            //small radius
-                            let r = config.small_radius;
-                            let rad = CAR_SPEED as f64 / r;
-                            let deg = rad.to_degrees();
-                            self.deg -= deg;
-                            self.rad -= rad;
-                            if self.deg <= 90.0 {
-                                self.direction = Location::South;
-                                self.destination = Destination::Ahead;
-                                self.deg = 90.0;
-                                self.rad = self.deg.to_radians();
-                                return;
-                            }
-                            let a = 180_f64.to_radians() - self.rad;
-                            let dx = r * a.sin();
-                            let x = SCREEN_WIDTH_F64 / 2.0
-                                + CAR_SIZE_F64
-                                + MARGIN_F64
-                                + CAR_SIZE_F64 / 2.0
-                                - dx;
-                            let dy = r - r * a.cos();
-                            let y = SCREEN_HEIGHT_F64 / 2.0
-                                + MARGIN_F64 / 2.0
-                                + CAR_SIZE_F64 / 2.0
-                                + dy;
-                            self.position.x = x;
-                            self.position.y = y;
-                            return;
-                        }
-                    }
-                    Destination::Right => {
-                        if self.position.x - CAR_SIZE_F64 / 2.0 <= config.top_right.x {
-                            //big radius
-                            let r = config.big_radius;
-                            let rad = CAR_SPEED as f64 / r;
-                            let deg = rad.to_degrees();
-                            self.deg += deg;
-                            self.rad += rad;
-                            if self.deg.abs() >= 270.0 {
-                                self.direction = Location::North;
-                                self.destination = Destination::Ahead;
-                                self.deg = 270.0;
-                                self.rad = self.deg.to_radians();
-                                return;
-                            }
-                            let a = (180_f64.to_radians() - self.rad).abs();
-                            let dx = r * a.sin();
-                            let x = SCREEN_WIDTH_F64 / 2.0
-                                + CAR_SIZE_F64
-                                + MARGIN_F64
-                                + CAR_SIZE_F64 / 2.0
-                                - dx;
-                            let dy = r - r * a.cos();
-                            let y = SCREEN_HEIGHT_F64 / 2.0 + MARGIN_F64 / 2.0 + CAR_SIZE_F64 / 2.0
-                                - dy;
-                            self.position.x = x;
-                            self.position.y = y;
-                            return;
-                        }
-                    }
-                    _ => {}
-                }
+        };
+        Some((
+            (from + angle).rem_euclid(TAU),
+            (sweep - angle) * ROUNDABOUT_RADIUS,
+        ))
+    }
+
+    //radians round the ring of a car that has left it but still sticks into it from the box
+    fn leaving_ring(&self, config: &Config) -> Option<(f64, f64)> {
+        let center = config.network.center(self.junction?);
+        let approaching = self
+            .stop_line_distance()
+            .is_some_and(|distance| distance > -POSITION_TOLERANCE);
+        if self.ring.is_some() || approaching || !self.is_in_crossing(config) {
+            return None;
+        }
+        Some((Layout::ring_angle(&center, &self.position), 0.0))
+    }
+
+    //a car on the ring reaches the point where the car joins within the critical gap,
+    //or stands too close to it, cars leaving the ring before that point do not count
+    fn must_give_way(&self, cars: &[Car], crossing: &Crossing, config: &Config) -> bool {
+        let (from, _) = self.ring_span(crossing, config);
+        cars.iter().any(|c| {
+            if c.id == self.id || c.junction != self.junction {
+                return false;
             }
-            Location::North => {
-                match self.destination {
-                    Destination::Left => {
-                        if self.position.y - CAR_SIZE_F64 / 2.0 <= config.bottom_left.y {
-                            //small radius
-                            let r = config.small_radius;
-                            let rad = CAR_SPEED as f64 / r;
-                            let deg = rad.to_degrees();
-                            self.deg -= deg;
-                            self.rad -= rad;
-                            if self.deg <= 180.0 {
-                                self.direction = Location::West;
-                                self.destination = Destination::Ahead;
-                                self.deg = 180.0;
-                                self.rad = self.deg.to_radians();
-                                return;
-                            }
-                            let a = (270_f64.to_radians() - self.rad).abs();
-                            let dx = r - r * a.cos();
-                            let x =
-                                SCREEN_WIDTH_F64 / 2.0 - MARGIN_F64 / 2.0 - CAR_SIZE_F64 / 2.0 - dx;
-                            let dy = r * a.sin();
-                            let y = SCREEN_HEIGHT_F64 / 2.0
-                                + CAR_SIZE_F64
-                                + MARGIN_F64
-                                + CAR_SIZE_F64 / 2.0
-                                - dy;
-                            self.position.x = x;
-                            self.position.y = y;
-                            return;
-                        }
-                    }
-                    Destination::Right => {
-                        if self.position.y - CAR_SIZE_F64 / 2.0 <= config.bottom_left.y {
-                            //big radius
-                            let r = config.big_radius;
-                            let rad = CAR_SPEED as f64 / r;
-                            let deg = rad.to_degrees();
-                            self.deg += deg;
-                            self.rad += rad;
-                            if self.deg >= 360.0 {
-                                self.direction = Location::East;
-                                self.destination = Destination::Ahead;
-                                self.deg = 0.0;
-                                self.rad = self.deg.to_radians();
-                                return;
-                            }
-                            let a = (270_f64.to_radians() - self.rad).abs();
-                            let dx = r - r * a.cos();
-                            let x =
-                                SCREEN_WIDTH_F64 / 2.0 - MARGIN_F64 / 2.0 - CAR_SIZE_F64 / 2.0 + dx;
-                            let dy = r * a.sin();
-                            let y = SCREEN_HEIGHT_F64 / 2.0
-                                + CAR_SIZE_F64
-                                + MARGIN_F64
-                                + CAR_SIZE_F64 / 2.0
-                                - dy;
-                            self.position.x = x;
-                            self.position.y = y;
-                            return;
-                        }
-                    }
-                    _ => {}
+            let Some((angle, left)) = c.ring_position(config) else {
+                return false;
+            };
+            let upstream = (from - angle).rem_euclid(TAU) * ROUNDABOUT_RADIUS;
+            let downstream = (angle - from).rem_euclid(TAU) * ROUNDABOUT_RADIUS;
+            let room = (self.length() + c.length()) / 2.0 + SEPARATION_DISTANCE;
+            downstream < room
+                || upstream < room
+                || (left >= upstream
+                    && c.seconds_to(upstream - room, config) < ROUNDABOUT_CRITICAL_GAP)
+        })
+    }
+
+    //drive speed pixels along the arc from its start onto the exit lane
+    fn turn_step(&mut self, config: &Config) {
+        let Some(junction) = self.junction else {
+            return;
+        };
+        let crossing = &config.crossings[junction];
+        let radius = config
+            .layout
+            .turn_radius(crossing, &self.lane, self.kind, &self.exit);
+        let start = self.lane.point(self.turn_start);
+        let (dx, dy) = self.lane.heading;
+        //towards the centre of the arc, left of the lane for near side turns
+        let (sx, sy) = if self.bend < 0.0 {
+            (dy, -dx)
+        } else {
+            (-dy, dx)
+        };
+        let entry = crossing.inbound(&self.origin);
+        let turned = (self.deg - entry).abs().to_radians() + self.speed / radius;
+        if turned >= self.bend.abs().to_radians() {
+            self.position = self.turn_end(crossing, config);
+            self.deg = crossing.angle(&self.exit);
+            self.rad = self.deg.to_radians();
+            self.direction = self.exit.clone();
+            self.destination = Destination::Ahead;
+            self.bend = 0.0;
+            return;
+        }
+        let forward = radius * turned.sin();
+        let sideways = radius * (1.0 - turned.cos());
+        self.position = PointF::new(
+            start.x + forward * dx + sideways * sx,
+            start.y + forward * dy + sideways * sy,
+        );
+        //the near side turn turns the car anticlockwise on screen
+        self.rad = if self.bend < 0.0 {
+            entry.to_radians() - turned
+        } else {
+            entry.to_radians() + turned
+        };
+        self.deg = self.rad.to_degrees();
+    }
+
+    //on the arc from its start onto the exit lane
+    pub fn is_turning(&self) -> bool {
+        self.bend != 0.0
+            && self
+                .turn_distance()
+                .is_some_and(|distance| distance <= POSITION_TOLERANCE)
+    }
+
+    //pixels left to the start of the arc, negative past it, as for Car::stop_line_distance
+    fn turn_distance(&self) -> Option<f64> {
+        self.stop_line_distance()
+            .map(|distance| distance + (self.turn_start - self.stop_line))
+    }
+
+    //pixels driven along the lane and then along the arc of the turn
+    fn progress(&self, config: &Config) -> f64 {
+        let (true, Some(junction)) = (self.is_turning(), self.junction) else {
+            return self.lane.along(&self.position);
+        };
+        let crossing = &config.crossings[junction];
+        let radius = config
+            .layout
+            .turn_radius(crossing, &self.lane, self.kind, &self.exit);
+        let turned = (self.deg - crossing.inbound(&self.origin))
+            .abs()
+            .to_radians();
+        self.turn_start + radius * turned
+    }
+
+    //point on the exit lane where the arc from its start ends
+    fn turn_end(&self, crossing: &Crossing, config: &Config) -> PointF {
+        config
+            .layout
+            .turn_end(crossing, &self.lane, self.kind, &self.exit)
+    }
+
+    //lane the car leaves crossing on
+    fn exit_lane(&self, crossing: &Crossing, config: &Config) -> Lane {
+        if self.bend == 0.0 {
+            return self.lane.clone();
+        }
+        Lane::new(
+            self.exit.clone(),
+            crossing.heading(&self.exit),
+            &self.turn_end(crossing, config),
+        )
+    }
+
+    //the road to the next crossing has no room left for the car to stand in,
+    //entering would leave the car stuck in the box
+    pub fn is_exit_blocked(&self, cars: &[Car], config: &Config) -> bool {
+        let Some(junction) = self.junction else {
+            return false;
+        };
+        let exit = self.exit.clone();
+        //cars leaving the screen never queue
+        let Some(next) = config.network.next(junction, &exit) else {
+            return false;
+        };
+        let crossing = &config.crossings[junction];
+        let center = &crossing.center;
+        let lane = self.exit_lane(crossing, config);
+        let entry = lane.along(center) - crossing.edge(&exit) - CAR_SIZE_F64 / 2.0;
+        //where the fronts of the cars stand at the next crossing, bicycles in the bike box ahead of it
+        //leave the road behind free
+        let line = config
+            .layout
+            .stop_line(&config.crossings[next], &lane, VehicleKind::Car, &exit)
+            + CAR_SIZE_F64 / 2.0
+            - config.layout.hold_back(VehicleKind::Car);
+        let edge = lane.along(center) + crossing.edge(&exit) + config.layout.zebra_width();
+        //vehicles crossing towards that road or already on it
+        let claimed: f64 = cars
+            .iter()
+            .filter(|c| {
+                let along = lane.along(&c.position);
+                let on_road = c.direction == exit
+                    && lane.covers(&c.position)
+                    && along > entry + POSITION_TOLERANCE
+                    && along + c.length() / 2.0 <= line + POSITION_TOLERANCE;
+                let turning_onto = c.junction == self.junction
+                    && c.is_turning()
+                    && c.exit_lane(crossing, config).beside(&lane).abs() < POSITION_TOLERANCE
+                    && c.exit == exit;
+                c.id != self.id && (on_road || turning_onto)
+            })
+            .map(|c| c.length() + config.following.standstill_gap())
+            .sum();
+        //standing bumper to bumper from the line back to the box they leave no room for the car
+        claimed + self.length() > line - edge
+    }
+
+    //head for the next crossing once the car has left the box of the one it crossed
+    pub fn pass_junction(&mut self, cars: &[Car], config: &Config) {
+        let Some(junction) = self.junction else {
+            return;
+        };
+        //the rear of the car has to be out of the box and off the zebra on the far side
+        let lane = Lane::of(self);
+        let crossing = &config.crossings[junction];
+        let far_edge = lane.along(&crossing.center)
+            + crossing.edge(&self.direction)
+            + config.layout.zebra_width();
+        if self.approach().is_some()
+            || self.is_in_crossing(config)
+            || lane.along(&self.position) - self.length() / 2.0 < far_edge - POSITION_TOLERANCE
+        {
+            return;
+        }
+        self.junction = config.network.next(junction, &self.direction);
+        let Some(next) = self.junction else {
+            return;
+        };
+        let crossing = &config.crossings[next];
+        self.origin = self.direction.opposite();
+        self.lane = Lane::of(self);
+        let mut turn = self.route.turns.pop_front().unwrap_or(Destination::Ahead);
+        let turns = config
+            .layout
+            .index_of(crossing, &self.lane)
+            .map(|index| config.layout.lanes[index].clone())
+            .unwrap_or_default();
+        let open = config.network.turns(next, &self.direction);
+        if !open.contains(&turn) {
+            //no arm that way, find a new way to the goal or take a turn the lane allows
+            let route = self
+                .route
+                .goal
+                .as_ref()
+                .and_then(|goal| config.network.route(next, &self.direction, goal));
+            turn = match route {
+                Some(mut route) => {
+                    let first = route.turns.pop_front();
+                    self.route = route;
+                    first
                 }
+                None => open.iter().find(|turn| turns.contains(turn)).cloned(),
             }
-            Location::South => {
-                match self.destination {
-                    Destination::Left => {
-                        if self.position.y + CAR_SIZE_F64 / 2.0 >= config.top_right.y {
-                            //small radius
-                            let r = config.small_radius;
-                            let rad = CAR_SPEED as f64 / r;
-                            let deg = rad.to_degrees();
-                            self.deg -= deg;
-                            self.rad -= rad;
-                            if self.deg <= 0.0 {
-                                self.direction = Location::East;
-                                self.destination = Destination::Ahead;
-                                self.deg = 0.0;
-                                self.rad = self.deg.to_radians();
-                                return;
-                            }
-                            let a = (90_f64.to_radians() - self.rad).abs();
-                            let dx = r - r * a.cos();
-                            let x =
-                                SCREEN_WIDTH_F64 / 2.0 + MARGIN_F64 / 2.0 + CAR_SIZE_F64 / 2.0 + dx;
-                            let dy = r * a.sin();
-                            let y = SCREEN_HEIGHT_F64 / 2.0
-                                - MARGIN_F64
-                                - CAR_SIZE_F64
-                                - CAR_SIZE_F64 / 2.0
-                                + dy;
-                            self.position.x = x;
-                            self.position.y = y;
-                            return;
-                        }
-                    }
-                    Destination::Right => {
-                        if self.position.y + CAR_SIZE_F64 / 2.0 >= config.top_right.y {
-                            //big radius
-                            let r = config.big_radius;
-                            let rad = CAR_SPEED as f64 / r;
-                            let deg = rad.to_degrees();
-                            self.deg += deg;
-                            self.rad += rad;
-                            if self.deg >= 180.0 {
-                                self.direction = Location::West;
-                                self.destination = Destination::Ahead;
-                                self.deg = 180.0;
-                                self.rad = self.deg.to_radians();
-                                return;
-                            }
-                            let a = (90_f64.to_radians() - self.rad).abs();
-                            let dx = r - r * a.cos();
-                            let x =
-                                SCREEN_WIDTH_F64 / 2.0 + MARGIN_F64 / 2.0 + CAR_SIZE_F64 / 2.0 - dx;
-                            let dy = r * a.sin();
-                            let y = SCREEN_HEIGHT_F64 / 2.0
-                                - MARGIN_F64
-                                - CAR_SIZE_F64
-                                - CAR_SIZE_F64 / 2.0
-                                + dy;
-                            self.position.x = x;
-                            self.position.y = y;
-                            return;
-                        }
+            .or(open.first().cloned())
+            .unwrap_or(Destination::Ahead);
+        }
+        if !turns.contains(&turn) {
+            //move over to the nearest lane for the turn if there is room beside the car
+            let beside = config
+                .layout
+                .lanes_for(&turn)
+                .into_iter()
+                .map(|index| config.layout.approach(crossing, &self.origin, index))
+                .filter(|lane| !self.is_beside(cars, lane))
+                .min_by(|a, b| {
+                    let a = a.beside(&self.lane).abs();
+                    let b = b.beside(&self.lane).abs();
+                    a.total_cmp(&b)
+                });
+            match beside {
+                Some(lane) => {
+                    self.position = lane.point(lane.along(&self.position));
+                    self.lane = lane;
+                }
+                //otherwise go ahead if the lane allows it and find a new way to the goal from there
+                None => {
+                    let ahead = Destination::Ahead;
+                    turn = if turns.contains(&ahead) && open.contains(&ahead) {
+                        ahead
+                    } else {
+                        turns
+                            .iter()
+                            .find(|turn| open.contains(turn))
+                            .cloned()
+                            .unwrap_or(ahead)
+                    };
+                    if let Some(goal) = self.route.goal.clone() {
+                        self.route = config
+                            .network
+                            .turned(next, &self.direction, &turn)
+                            .and_then(|out| {
+                                let after = config.network.next(next, &out)?;
+                                config.network.route(after, &out, &goal)
+                            })
+                            .unwrap_or_default();
                     }
-                    _ => {}
                 }
             }
         }
+        self.exit = config
+            .network
+            .turned(next, &self.direction, &turn)
+            .unwrap_or_else(|| self.direction.turned(&turn));
+        self.bend = crossing.bend(&self.origin, &self.exit);
+        self.stop_line = config
+            .layout
+            .stop_line(crossing, &self.lane, self.kind, &self.exit);
+        self.turn_start = config
+            .layout
+            .turn_start(crossing, &self.lane, self.kind, &self.exit);
+        self.turn = turn.clone();
+        self.destination = turn;
+        self.color = ColorOrUrl::of(self.kind, &self.turn);
+    }
 
-        match self.direction {
-            Location::East => {
-                self.position.x += CAR_SPEED_F64;
-            }
-            Location::West => {
-                self.position.x -= CAR_SPEED_F64;
+    //a car next to or just ahead or behind the car on lane, vehicles still on their arc
+    //count with the part of them that reaches over the lane
+    fn is_beside(&self, cars: &[Car], lane: &Lane) -> bool {
+        let mut moved = Obb::of(self);
+        moved.center = lane.point(lane.along(&self.position));
+        moved.half_length += SEPARATION_DISTANCE;
+        cars.iter()
+            .any(|c| c.id != self.id && moved.overlaps(&Obb::of(c)))
+    }
+
+    //give way to conflicting cars inside the crossing and to the first car of a
+    //conflicting approach if it has right of way and reaches its line within the critical gap,
+    //cars queued behind it can not get there first
+    pub fn must_yield(
+        &self,
+        cars: &[Car],
+        traffic_lights: &HashMap<Location, TrafficLight>,
+        config: &Config,
+    ) -> bool {
+        let movement = Movement::of(self);
+        cars.iter().any(|c| {
+            if c.id == self.id || c.junction != self.junction || c.origin == self.origin {
+                return false;
             }
-            Location::North => {
-                self.position.y -= CAR_SPEED_F64;
+            let other = Movement::of(c);
+            if !config.conflicts.conflicts(&movement, &other) {
+                return false;
             }
-            Location::South => {
-                self.position.y += CAR_SPEED_F64;
+            match c.stop_line_distance() {
+                Some(distance) if distance > -POSITION_TOLERANCE => {
+                    let goes_first = other.priority() > movement.priority()
+                        || (other.priority() == movement.priority() && c.id < self.id);
+                    goes_first
+                        && c.seconds_to(distance, config) < CRITICAL_GAP
+                        && traffic_lights[&c.origin].allows_entry(c)
+                        && c.leads_approach(cars)
+                }
+                _ => c.is_in_crossing(config) || c.is_entering(config),
             }
+        })
+    }
+
+    //no car of the same approach is closer to the stop line
+    pub fn leads_approach(&self, cars: &[Car]) -> bool {
+        let Some(distance) = self.stop_line_distance() else {
+            return false;
+        };
+        !cars.iter().any(|c| {
+            c.id != self.id
+                && c.junction == self.junction
+                && c.origin == self.origin
+                && c.stop_line_distance()
+                    .is_some_and(|other| other >= 0.0 && other < distance)
+        })
+    }
+
+    //pixels left to its stop line of an emergency vehicle close enough to call green for its approach,
+    //none for everything else
+    pub fn preemption_call(&self) -> Option<f64> {
+        if self.kind != VehicleKind::Emergency {
+            return None;
+        }
+        self.stop_line_distance()
+            .filter(|distance| *distance > -POSITION_TOLERANCE && *distance < PREEMPTION_DISTANCE)
+    }
+
+    //an emergency vehicle calls from another approach of the crossing while the car can still
+    //stop comfortably at its line, cars with one coming up behind them drive on to make way,
+    //emergency vehicles do not hold for each other
+    fn holds_for_emergency(&self, cars: &[Car]) -> bool {
+        let Some(distance) = self.stop_line_distance() else {
+            return false;
+        };
+        let mut calling = cars
+            .iter()
+            .filter(|c| c.junction == self.junction && c.preemption_call().is_some());
+        self.kind != VehicleKind::Emergency
+            && self.braking_distance() <= distance
+            && calling.clone().any(|c| c.origin != self.origin)
+            && !calling.any(|c| c.origin == self.origin)
+    }
+
+    //pixels left to where the car stops at its stop line, behind the bike box for cars that
+    //can still stop comfortably before it, distance as from Car::stop_line_distance,
+    //one tick of slack keeps a car braking onto the box from changing its mind
+    fn hold_distance(&self, distance: f64, config: &Config) -> f64 {
+        let hold = distance - config.layout.hold_back(self.kind);
+        if hold + self.speed + POSITION_TOLERANCE >= self.braking_distance() {
+            hold
+        } else {
+            distance
+        }
+    }
+
+    //pixels needed to stop from the current speed
+    pub fn braking_distance(&self) -> f64 {
+        let deceleration = COMFORTABLE_DECELERATION * TICK_DURATION * TICK_DURATION;
+        self.speed * self.speed / (2.0 * deceleration)
+    }
+
+    //seconds the car needs to drive distance pixels from its current speed,
+    //speeding up at full acceleration until it reaches its free speed
+    pub fn seconds_to(&self, distance: f64, config: &Config) -> f64 {
+        let speed = self.speed * TICKS_PER_SECOND;
+        let top = config.following.free_speed(self.kind) * TICKS_PER_SECOND;
+        let acceleration = self.kind.acceleration();
+        let speeding_up = (top - speed).max(0.0) / acceleration;
+        let covered = (speed + top) / 2.0 * speeding_up;
+        let distance = distance.max(0.0);
+        if distance <= covered {
+            return ((speed * speed + 2.0 * acceleration * distance).sqrt() - speed) / acceleration;
         }
+        speeding_up + (distance - covered) / top
+    }
+
+    //pixels left until the car stands at its stop line, negative once past it,
+    //none after the car has turned or left its last crossing
+    pub fn stop_line_distance(&self) -> Option<f64> {
+        self.junction?;
+        if let Some(circling) = &self.ring {
+            return Some(self.stop_line - circling.joined - circling.angle * ROUNDABOUT_RADIUS);
+        }
+        if self.direction != self.origin.opposite() {
+            return None;
+        }
+        Some(self.stop_line - self.lane.along(&self.position))
+    }
+
+    //pixels along its lane of the detectors one car length before the stop line and on it,
+    //where the centre of the car passes them
+    pub fn detectors(&self) -> [f64; 2] {
+        //the centre of a shorter vehicle reaches them later
+        let line = self.stop_line + (self.length() - CAR_SIZE_F64) / 2.0;
+        [line - CAR_SIZE_F64, line]
     }
 }
 
+//part of the ring of a roundabout a car has driven
+#[derive(Clone, Debug)]
+pub struct Circling {
+    //pixels along the lane where the car joined the ring
+    pub joined: f64,
+    //radians driven round since
+    pub angle: f64,
+}
+
 #[derive(Clone, Debug)]
 pub struct Point {
     pub x: i32,
@@ -796,16 +1361,118 @@ impl Dimen {
     }
 }
 
-#[derive(PartialEq, Clone, Eq, Hash, Debug)]
+//side of the screen or compass point an arm of a crossing leads to
+#[derive(PartialEq, Clone, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Location {
     South,
     North,
     East,
     West,
+    NorthEast,
+    SouthEast,
+    SouthWest,
+    NorthWest,
 }
 impl Location {
-    pub fn get_random() -> Location {
-        let r = rand::rng().random_range(0..4);
+    pub fn name(&self) -> &'static str {
+        match self {
+            Location::South => "south",
+            Location::North => "north",
+            Location::East => "east",
+            Location::West => "west",
+            Location::NorthEast => "northeast",
+            Location::SouthEast => "southeast",
+            Location::SouthWest => "southwest",
+            Location::NorthWest => "northwest",
+        }
+    }
+
+    //one or two letters for tight tables
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Location::South => "s",
+            Location::North => "n",
+            Location::East => "e",
+            Location::West => "w",
+            Location::NorthEast => "ne",
+            Location::SouthEast => "se",
+            Location::SouthWest => "sw",
+            Location::NorthWest => "nw",
+        }
+    }
+
+    pub fn opposite(&self) -> Location {
+        match self {
+            Location::East => Location::West,
+            Location::West => Location::East,
+            Location::North => Location::South,
+            Location::South => Location::North,
+            Location::NorthEast => Location::SouthWest,
+            Location::SouthWest => Location::NorthEast,
+            Location::SouthEast => Location::NorthWest,
+            Location::NorthWest => Location::SouthEast,
+        }
+    }
+
+    //unit vector of driving towards location, y grows down the screen
+    pub fn heading(&self) -> (f64, f64) {
+        Lane::unit(self.degrees())
+    }
+
+    //angle of a car driving towards location
+    pub fn degrees(&self) -> f64 {
+        match self {
+            Location::East => 0.0,
+            Location::SouthEast => 45.0,
+            Location::South => 90.0,
+            Location::SouthWest => 135.0,
+            Location::West => 180.0,
+            Location::NorthWest => 225.0,
+            Location::North => 270.0,
+            Location::NorthEast => 315.0,
+        }
+    }
+
+    //where a car driving towards location is heading after turn on the compass,
+    //crossings with arms off it classify their turns with Network::turned
+    pub fn turned(&self, turn: &Destination) -> Location {
+        let quarter = match turn {
+            Destination::Ahead => return self.clone(),
+            Destination::Left | Destination::BearLeft => 270.0,
+            Destination::Right | Destination::BearRight => 90.0,
+        };
+        let degrees = (self.degrees() + quarter).rem_euclid(360.0);
+        Location::compass()
+            .into_iter()
+            .find(|location| location.degrees() == degrees)
+            .unwrap_or_else(|| self.clone())
+    }
+
+    //the four sides of the screen
+    pub fn all() -> Vec<Location> {
+        vec![
+            Location::West,
+            Location::North,
+            Location::East,
+            Location::South,
+        ]
+    }
+
+    //the four sides and the corners between them
+    pub fn compass() -> Vec<Location> {
+        let mut locations = Self::all();
+        locations.extend([
+            Location::NorthWest,
+            Location::NorthEast,
+            Location::SouthEast,
+            Location::SouthWest,
+        ]);
+        locations
+    }
+
+    pub fn get_random(rng: &mut impl Rng) -> Location {
+        let r = rng.random_range(0..4);
         match r {
             0 => Location::West,
             1 => Location::East,
@@ -815,16 +1482,41 @@ impl Location {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(PartialEq, Clone, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Destination {
     Ahead,
     Left,
     Right,
+    //the gentler of two turns to the same side, at crossings with more than four arms
+    BearLeft,
+    BearRight,
 }
 
 impl Destination {
-    pub fn get_random() -> Destination {
-        let r = rand::rng().random_range(0..3);
+    pub fn name(&self) -> &'static str {
+        match self {
+            Destination::Ahead => "ahead",
+            Destination::Left => "left",
+            Destination::Right => "right",
+            Destination::BearLeft => "bearleft",
+            Destination::BearRight => "bearright",
+        }
+    }
+
+    //every turn from the sharpest near side one to the sharpest far side one
+    pub fn all() -> [Destination; 5] {
+        [
+            Destination::Left,
+            Destination::BearLeft,
+            Destination::Ahead,
+            Destination::BearRight,
+            Destination::Right,
+        ]
+    }
+
+    pub fn get_random(rng: &mut impl Rng) -> Destination {
+        let r = rng.random_range(0..3);
         match r {
             0 => Destination::Left,
             1 => Destination::Right,
@@ -840,75 +1532,244 @@ pub struct Line {
     pub color: (u8, u8, u8),
 }
 
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SignalState {
+    Red,
+    RedAmber,
+    Green,
+    Amber,
+    //give way, no light is in charge
+    FlashingAmber,
+}
+
+impl SignalState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SignalState::Red => "red",
+            SignalState::RedAmber => "red-amber",
+            SignalState::Green => "green",
+            SignalState::Amber => "amber",
+            SignalState::FlashingAmber => "flashing",
+        }
+    }
+}
+
 pub struct TrafficLight {
     pub location: Location,
     pub size: Dimen,
-    pub status: bool,
+    pub state: SignalState,
+    //ticks since state changed
+    pub elapsed: u64,
+    //centre of its crossing
+    pub center: Point,
+    //pixels from the centre of the crossing to the stop line
+    pub edge: i32,
+    //degrees the arm of the light leaves the centre at, clockwise on screen from east
+    pub angle: f64,
 }
 impl TrafficLight {
     pub fn new(location: Location) -> Self {
         TrafficLight {
+            angle: location.degrees(),
             location,
             size: Dimen::new(TRAFFIC_LIGHTS_WIDTH, TRAFFIC_LIGHTS_HEIGTH),
-            status: false,
+            state: SignalState::Red,
+            elapsed: 0,
+            center: Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2),
+            edge: CAR_SIZE + MARGIN,
+        }
+    }
+
+    pub fn set_state(&mut self, state: SignalState) {
+        if self.state != state {
+            self.state = state;
+            self.elapsed = 0;
+        }
+    }
+
+    //seconds since state changed
+    pub fn elapsed_time(&self) -> f64 {
+        self.elapsed as f64 * TICK_DURATION
+    }
+
+    //may a car standing at the stop line enter the crossing
+    pub fn allows_entry(&self, car: &Car) -> bool {
+        match self.state {
+            SignalState::Green => true,
+            SignalState::Red | SignalState::RedAmber => false,
+            //go only if the car is too close to stop comfortably,
+            //one tick of slack keeps a braking car from changing its mind
+            //a car waiting on the line to turn across oncoming traffic clears on amber
+            SignalState::Amber => {
+                let distance = car.stop_line_distance().unwrap_or(0.0);
+                let across = matches!(car.turn, Destination::Right | Destination::BearRight);
+                let waiting_to_turn = across && car.is_stopped && distance < DETECTOR_LENGTH;
+                waiting_to_turn || car.braking_distance() > distance + car.speed
+            }
+            //drivers sort out who goes first themselves
+            SignalState::FlashingAmber => true,
         }
     }
 }
 
 pub struct TrafficLightSwitch {
-    pub request: Option<Location>,
     pub traffic_lights: HashMap<Location, TrafficLight>,
+    //signal of the zebra on each arm
+    pub walk: HashMap<Location, WalkSignal>,
+    //every light red and people crossing every arm
+    pub pedestrian_phase: bool,
+    pub timing: SignalTiming,
+    //seconds the signal plan of this crossing runs behind the common cycle
+    pub offset: f64,
+    //emergency vehicle the controller has handed the lights over to
+    pub preemption: Option<Preemption>,
+}
+
+//an emergency vehicle calling green for its approach
+#[derive(Clone, Debug)]
+pub struct Preemption {
+    pub approach: Location,
+    //ticks since the call
+    pub elapsed: u64,
+    //its approach has shown green since the call
+    pub served: bool,
 }
 
 impl TrafficLightSwitch {
-    pub fn create_traffic_lights() -> HashMap<Location, TrafficLight> {
-        let mut lights = HashMap::new();
-        lights.insert(Location::West, TrafficLight::new(Location::West));
-        lights.insert(Location::North, TrafficLight::new(Location::North));
-        lights.insert(Location::South, TrafficLight::new(Location::South));
-        lights.insert(Location::East, TrafficLight::new(Location::East));
-        lights
+    //one light for each arm
+    pub fn create_traffic_lights(arms: &[Location]) -> HashMap<Location, TrafficLight> {
+        arms.iter()
+            .map(|arm| (arm.clone(), TrafficLight::new(arm.clone())))
+            .collect()
     }
 
-    pub fn request(&mut self, location: Location) {
-        self.request = Some(location);
+    //one walk signal for each arm
+    pub fn create_walk_signals(arms: &[Location]) -> HashMap<Location, WalkSignal> {
+        arms.iter()
+            .map(|arm| (arm.clone(), WalkSignal::DontWalk))
+            .collect()
     }
-    pub fn urgent_request(&mut self, location: Location) {
-        self.traffic_lights
-            .entry(Location::West)
-            .and_modify(|v| v.status = false);
-        self.traffic_lights
-            .entry(Location::East)
-            .and_modify(|v| v.status = false);
-        self.traffic_lights
-            .entry(Location::North)
-            .and_modify(|v| v.status = false);
+
+    pub fn tick(&mut self) {
+        for light in self.traffic_lights.values_mut() {
+            light.elapsed += 1;
+        }
+        for (side, signal) in self.walk.iter_mut() {
+            *signal = WalkSignal::of(side, &self.traffic_lights, self.pedestrian_phase, *signal);
+        }
+        if let Some(preemption) = &mut self.preemption {
+            preemption.elapsed += 1;
+        }
+    }
+
+    //take the lights over from the controller while emergency vehicles call from calls, nearest first,
+    //staying with the approach already called while it still calls,
+    //its green is forced so every other approach clears through amber and all red first
+    pub fn preempt(&mut self, calls: &[Location]) -> Option<Decision> {
+        self.preemption = match self.preemption.take() {
+            Some(preemption) if calls.contains(&preemption.approach) => Some(preemption),
+            _ => calls.first().map(|approach| Preemption {
+                approach: approach.clone(),
+                elapsed: 0,
+                served: false,
+            }),
+        };
+        self.preemption
+            .as_ref()
+            .map(|preemption| Decision::Force(vec![preemption.approach.clone()]))
+    }
+
+    //seconds from the call to green on the tick the called approach first shows it
+    pub fn response_time(&mut self) -> Option<f64> {
+        let preemption = self.preemption.as_mut()?;
+        let green = self
+            .traffic_lights
+            .get(&preemption.approach)
+            .is_some_and(|light| light.state == SignalState::Green);
+        if preemption.served || !green {
+            return None;
+        }
+        preemption.served = true;
+        Some(preemption.elapsed as f64 * TICK_DURATION)
+    }
+
+    //start amber on every light that lets cars go,
+    //flashing ones give no right of way so they stop before any green too
+    fn stop_all_except(&mut self, locations: &[Location]) {
+        for light in self.traffic_lights.values_mut() {
+            let stops = match light.state {
+                SignalState::Green | SignalState::RedAmber => !locations.contains(&light.location),
+                SignalState::FlashingAmber => true,
+                _ => false,
+            };
+            if stops {
+                light.set_state(SignalState::Amber);
+            }
+        }
+    }
+
+    //every other light red for at least all_red seconds
+    fn is_all_red_except(&self, locations: &[Location]) -> bool {
         self.traffic_lights
-            .entry(Location::South)
-            .and_modify(|v| v.status = false);
-        self.request = Some(location);
-    }
-    pub fn update(&mut self, cars: Vec<Car>) {
-        if let Some(location) = &self.request {
-            if !self.traffic_lights[&location].status {
-                if Model::is_crossing_clear(cars) {
-                    self.traffic_lights
-                        .entry(Location::West)
-                        .and_modify(|v| v.status = false);
-                    self.traffic_lights
-                        .entry(Location::East)
-                        .and_modify(|v| v.status = false);
-                    self.traffic_lights
-                        .entry(Location::North)
-                        .and_modify(|v| v.status = false);
-                    self.traffic_lights
-                        .entry(Location::South)
-                        .and_modify(|v| v.status = false);
+            .values()
+            .filter(|light| !locations.contains(&light.location))
+            .all(|light| {
+                light.state == SignalState::Red && light.elapsed_time() >= self.timing.all_red
+            })
+    }
+
+    //move lights towards what the controller decided
+    pub fn apply(&mut self, decision: &Decision, crossing_clear: bool) {
+        let timing = self.timing.clone();
+        for light in self.traffic_lights.values_mut() {
+            match light.state {
+                SignalState::Amber if light.elapsed_time() >= timing.amber => {
+                    light.set_state(SignalState::Red)
+                }
+                SignalState::RedAmber if light.elapsed_time() >= timing.red_amber => {
+                    light.set_state(SignalState::Green)
+                }
+                _ => {}
+            }
+        }
+        let green = match decision {
+            Decision::Keep => return,
+            Decision::Flashing => {
+                self.pedestrian_phase = false;
+                for light in self.traffic_lights.values_mut() {
+                    light.set_state(SignalState::FlashingAmber);
+                }
+                return;
+            }
+            Decision::Request(green) => {
+                let waiting = green.iter().any(|location| {
                     self.traffic_lights
-                        .entry(location.clone())
-                        .and_modify(|v| v.status = true);
-                    self.request = None;
+                        .get(location)
+                        .is_some_and(|light| light.state == SignalState::Red)
+                });
+                if !waiting || !crossing_clear {
+                    return;
+                }
+                green
+            }
+            Decision::Force(green) => green,
+            Decision::Walk => {
+                self.stop_all_except(&[]);
+                if self.is_all_red_except(&[]) && crossing_clear {
+                    self.pedestrian_phase = true;
                 }
+                return;
+            }
+        };
+        self.stop_all_except(green);
+        if self.is_all_red_except(green) && crossing_clear {
+            self.pedestrian_phase = false;
+            for location in green {
+                self.traffic_lights.entry(location.clone()).and_modify(|v| {
+                    if v.state == SignalState::Red {
+                        v.set_state(SignalState::RedAmber);
+                    }
+                });
             }
         }
     }
@@ -920,11 +1781,79 @@ pub struct ColorOrUrl {
     pub url: String,
 }
 
+impl ColorOrUrl {
+    //vehicles show the turn they take next
+    pub fn of(kind: VehicleKind, destination: &Destination) -> Self {
+        let color = match destination {
+            Destination::Ahead => CAR_COLOR_WHITE,
+            Destination::Right | Destination::BearRight => CAR_COLOR_ORANGE,
+            Destination::Left | Destination::BearLeft => CAR_COLOR_BLUE,
+        };
+        ColorOrUrl {
+            color,
+            url: String::from(kind.sprite(destination)),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Config {
-    pub small_radius: f64,
-    pub big_radius: f64,
-    pub top_left: PointF,
-    pub top_right: PointF,
-    pub bottom_left: PointF,
-    pub bottom_right: PointF,
+    pub following: CarFollowing,
+    pub layout: Layout,
+    pub network: Network,
+    //boxes and arms of the crossings in network order
+    pub crossings: Vec<Crossing>,
+    //movements that cross or merge inside a crossing, the same at every crossing
+    pub conflicts: Rc<ConflictTable>,
+}
+
+impl Config {
+    pub fn new(following: CarFollowing, layout: Layout, network: Network) -> Self {
+        let conflicts = ConflictTable::shared(&layout, &network);
+        let crossings = (0..network.junctions())
+            .map(|index| network.crossing(index, &layout))
+            .collect();
+        Self {
+            following,
+            layout,
+            network,
+            crossings,
+            conflicts,
+        }
+    }
+}
+
+//one crossing of the network with its own lights
+pub struct Junction {
+    pub center: PointF,
+    pub traffic_light_switch: TrafficLightSwitch,
+}
+
+impl Junction {
+    pub fn create_junctions(config: &Config) -> Vec<Junction> {
+        (0..config.network.junctions())
+            .map(|index| {
+                let crossing = &config.crossings[index];
+                let center = crossing.center.clone();
+                let arms = config.network.arms(index);
+                let mut traffic_lights = TrafficLightSwitch::create_traffic_lights(&arms);
+                for light in traffic_lights.values_mut() {
+                    light.center = Point::new(center.x.round() as i32, center.y.round() as i32);
+                    light.edge = crossing.edge(&light.location) as i32;
+                    light.angle = crossing.angle(&light.location);
+                }
+                Junction {
+                    center,
+                    traffic_light_switch: TrafficLightSwitch {
+                        traffic_lights,
+                        walk: TrafficLightSwitch::create_walk_signals(&arms),
+                        pedestrian_phase: false,
+                        timing: SignalTiming::default(),
+                        offset: 0.0,
+                        preemption: None,
+                    },
+                }
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,531 @@
+use crate::constants::*;
+use crate::lane::Lane;
+use crate::layout::{Crossing, Layout};
+use crate::model::{Destination, Location, PointF};
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+//crossings on a grid spread evenly over the screen, numbered row by row from the top left
+#[derive(Deserialize, Debug, Clone)]
+pub struct Network {
+    #[serde(default = "Network::default_size")]
+    pub columns: usize,
+    #[serde(default = "Network::default_size")]
+    pub rows: usize,
+    //sides each crossing has an arm on in network order, crossings not listed have all four,
+    //arms off the corners of the compass only on networks of one crossing
+    #[serde(default)]
+    pub arms: Vec<Vec<Location>>,
+    //degrees clockwise on screen from east the arm on a side leaves at, if not where the compass
+    //points, at most MAX_ARM_SKEW off it and only on networks of one crossing
+    #[serde(default)]
+    pub angles: HashMap<Location, f64>,
+}
+
+//where a road meets the side of the screen, roads counted from the top or the left
+#[derive(PartialEq, Clone, Debug)]
+pub struct Edge {
+    pub side: Location,
+    pub road: usize,
+}
+
+//turns still to take at the crossings ahead and where the car wants to leave the screen,
+//cars without a goal go ahead once their turns are used up
+#[derive(Clone, Debug, Default)]
+pub struct Route {
+    pub turns: VecDeque<Destination>,
+    pub goal: Option<Edge>,
+}
+
+impl Route {
+    //one turn at the first crossing and straight on after it
+    pub fn turn(turn: Destination) -> Self {
+        Self {
+            turns: VecDeque::from([turn]),
+            goal: None,
+        }
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self {
+            columns: Self::default_size(),
+            rows: Self::default_size(),
+            arms: vec![],
+            angles: HashMap::new(),
+        }
+    }
+}
+
+impl Network {
+    fn default_size() -> usize {
+        1
+    }
+
+    pub fn validate(&self, layout: &Layout) -> Result<(), String> {
+        if self.columns == 0 || self.rows == 0 {
+            return Err(String::from("a network needs at least one row and column"));
+        }
+        //road left between two crossings, half of it on the way in from the edge of the screen
+        let (width, height) = self.spacing();
+        let road = width.min(height)
+            - 2.0 * (layout.half_width() + layout.zebra_width() + layout.bike_box_depth());
+        if road < MIN_ROAD_LENGTH {
+            return Err(format!(
+                "{} x {} crossings with {} lanes leave {:.0} px of road between them, need {:.0}",
+                self.columns,
+                self.rows,
+                layout.lanes.len(),
+                road,
+                MIN_ROAD_LENGTH
+            ));
+        }
+        if self.is_skewed() && self.junctions() > 1 {
+            return Err(String::from(
+                "arms off the four sides or turned away from them need a network of one crossing",
+            ));
+        }
+        for (side, angle) in &self.angles {
+            if !angle.is_finite() || !(0.0..360.0).contains(angle) {
+                return Err(format!(
+                    "the {} arm leaves at {} degrees, need 0 up to 360",
+                    side.name(),
+                    angle
+                ));
+            }
+            let skew = Self::normalize(angle - side.degrees());
+            if skew.abs() > MAX_ARM_SKEW {
+                return Err(format!(
+                    "the {} arm leaves {:.0} degrees off {}, at most {:.0}",
+                    side.name(),
+                    skew.abs(),
+                    side.name(),
+                    MAX_ARM_SKEW
+                ));
+            }
+        }
+        if self.arms.len() > self.junctions() {
+            return Err(format!(
+                "arms for {} crossings, there are {}",
+                self.arms.len(),
+                self.junctions()
+            ));
+        }
+        for index in 0..self.junctions() {
+            if self.arms(index).len() < 3 {
+                return Err(format!("crossing {} needs at least three arms", index));
+            }
+            //a road between two crossings needs an arm at both ends
+            for side in Location::all() {
+                if let Some(next) = self.neighbour(index, &side) {
+                    if self.has_arm(index, &side) != self.has_arm(next, &side.opposite()) {
+                        return Err(format!(
+                            "the road between crossings {} and {} has an arm at one end only",
+                            index, next
+                        ));
+                    }
+                }
+            }
+            //neighbouring arms far enough apart for their kerbs to meet,
+            //and near enough to close the box between them
+            let arms = self.arm_angles(index);
+            for (at, (side, angle)) in arms.iter().enumerate() {
+                let (next, following) = &arms[(at + 1) % arms.len()];
+                let gap = (following - angle).rem_euclid(360.0);
+                if gap < MIN_ARM_GAP {
+                    return Err(format!(
+                        "the {} and {} arms of crossing {} are {:.0} degrees apart, need {:.0}",
+                        side.name(),
+                        next.name(),
+                        index,
+                        gap,
+                        MIN_ARM_GAP
+                    ));
+                }
+                if gap > 180.0 {
+                    return Err(format!(
+                        "crossing {} has no arm for {:.0} degrees from the {} arm, need one every 180",
+                        index,
+                        gap,
+                        side.name()
+                    ));
+                }
+            }
+            if self.is_skewed() {
+                for side in self.arms(index) {
+                    let road = self.approach_length(index, &side, layout);
+                    if road < MIN_ROAD_LENGTH / 2.0 {
+                        return Err(format!(
+                            "the {} arm of crossing {} leaves {:.0} px of road before the stop line, need {:.0}",
+                            side.name(),
+                            index,
+                            road,
+                            MIN_ROAD_LENGTH / 2.0
+                        ));
+                    }
+                }
+            }
+            //every lane of every arm must lead somewhere
+            for side in self.arms(index) {
+                if self.exits(index, &side.opposite()).len() + 1 < arms.len() {
+                    return Err(format!(
+                        "cars coming into crossing {} from the {} have more than two arms to one side",
+                        index,
+                        side.name()
+                    ));
+                }
+                let turns = self.turns(index, &side.opposite());
+                if let Some(turn) = turns.iter().find(|turn| layout.lanes_for(turn).is_empty()) {
+                    return Err(format!(
+                        "no lane for turning {} coming into crossing {} from the {}",
+                        turn.name(),
+                        index,
+                        side.name()
+                    ));
+                }
+                if let Some(lane) = layout
+                    .lanes
+                    .iter()
+                    .position(|lane| !lane.iter().any(|turn| turns.contains(turn)))
+                {
+                    return Err(format!(
+                        "lane {} coming into crossing {} from the {} has no way out",
+                        lane,
+                        index,
+                        side.name()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //crossing index has an arm on side
+    pub fn has_arm(&self, index: usize, side: &Location) -> bool {
+        match self.arms.get(index) {
+            Some(arms) => arms.contains(side),
+            None => Location::all().contains(side),
+        }
+    }
+
+    //sides crossing index has an arm on
+    pub fn arms(&self, index: usize) -> Vec<Location> {
+        Location::compass()
+            .into_iter()
+            .filter(|side| self.has_arm(index, side))
+            .collect()
+    }
+
+    //degrees clockwise on screen from east the arm on side leaves a crossing at
+    pub fn angle(&self, side: &Location) -> f64 {
+        self.angles
+            .get(side)
+            .copied()
+            .unwrap_or_else(|| side.degrees())
+    }
+
+    //sides crossing index has an arm on with the degrees each leaves at, clockwise from east
+    pub fn arm_angles(&self, index: usize) -> Vec<(Location, f64)> {
+        let mut arms: Vec<(Location, f64)> = self
+            .arms(index)
+            .into_iter()
+            .map(|side| {
+                let angle = self.angle(&side);
+                (side, angle)
+            })
+            .collect();
+        arms.sort_by(|a, b| a.1.total_cmp(&b.1));
+        arms
+    }
+
+    //any arm off the four sides of the screen or turned away from them
+    pub fn is_skewed(&self) -> bool {
+        self.angles
+            .iter()
+            .any(|(side, angle)| *angle != side.degrees())
+            || self
+                .arms
+                .iter()
+                .flatten()
+                .any(|side| !Location::all().contains(side))
+    }
+
+    //box and arms of crossing index
+    pub fn crossing(&self, index: usize, layout: &Layout) -> Crossing {
+        layout.crossing(self.center(index), self.arm_angles(index))
+    }
+
+    //degrees into -180 up to 180
+    fn normalize(degrees: f64) -> f64 {
+        let degrees = degrees.rem_euclid(360.0);
+        if degrees > 180.0 {
+            degrees - 360.0
+        } else {
+            degrees
+        }
+    }
+
+    //arms a car driving towards direction into crossing index may leave on and the turn onto each,
+    //the arm nearest straight on within 45 degrees is ahead, the sharpest turn to either side
+    //is left or right and the next one bears that way, arms beyond those are left out
+    fn exits(&self, index: usize, direction: &Location) -> Vec<(Destination, Location)> {
+        let origin = direction.opposite();
+        let heading = self.angle(&origin) + 180.0;
+        let mut others: Vec<(f64, Location)> = self
+            .arms(index)
+            .into_iter()
+            .filter(|side| *side != origin)
+            .map(|side| (Self::normalize(self.angle(&side) - heading), side))
+            .collect();
+        others.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut exits = vec![];
+        if let Some(ahead) = (0..others.len())
+            .filter(|at| others[*at].0.abs() < 45.0)
+            .min_by(|a, b| others[*a].0.abs().total_cmp(&others[*b].0.abs()))
+        {
+            exits.push((Destination::Ahead, others.remove(ahead).1));
+        }
+        let (near, far): (Vec<_>, Vec<_>) = others.into_iter().partition(|(bend, _)| *bend < 0.0);
+        exits.extend(
+            [Destination::Left, Destination::BearLeft]
+                .into_iter()
+                .zip(near.into_iter().map(|(_, side)| side)),
+        );
+        exits.extend(
+            [Destination::Right, Destination::BearRight]
+                .into_iter()
+                .zip(far.into_iter().rev().map(|(_, side)| side)),
+        );
+        exits
+    }
+
+    //side a car driving towards direction into crossing index leaves on after turn,
+    //none if no arm lies that way
+    pub fn turned(
+        &self,
+        index: usize,
+        direction: &Location,
+        turn: &Destination,
+    ) -> Option<Location> {
+        self.exits(index, direction)
+            .into_iter()
+            .find(|(exit, _)| exit == turn)
+            .map(|(_, side)| side)
+    }
+
+    //turns open to a car driving towards direction into crossing index, in the order
+    //Destination::get_random picks from so crossings with every arm draw the same turns,
+    //the gentler turns of crossings with more arms after them
+    pub fn turns(&self, index: usize, direction: &Location) -> Vec<Destination> {
+        let exits = self.exits(index, direction);
+        [
+            Destination::Left,
+            Destination::Right,
+            Destination::Ahead,
+            Destination::BearLeft,
+            Destination::BearRight,
+        ]
+        .into_iter()
+        .filter(|turn| exits.iter().any(|(exit, _)| exit == turn))
+        .collect()
+    }
+
+    pub fn random_turn(
+        &self,
+        index: usize,
+        direction: &Location,
+        rng: &mut impl Rng,
+    ) -> Destination {
+        let turns = self.turns(index, direction);
+        turns[rng.random_range(0..turns.len())].clone()
+    }
+
+    pub fn junctions(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    //pixels between the centres of two neighbouring crossings
+    fn spacing(&self) -> (f64, f64) {
+        (
+            SCREEN_WIDTH_F64 / self.columns as f64,
+            SCREEN_HEIGHT_F64 / self.rows as f64,
+        )
+    }
+
+    pub fn center(&self, index: usize) -> PointF {
+        let (width, height) = self.spacing();
+        let column = index % self.columns;
+        let row = index / self.columns;
+        PointF::new((column as f64 + 0.5) * width, (row as f64 + 0.5) * height)
+    }
+
+    //pixels from the first crossing of its row or column driving towards direction to crossing index
+    pub fn distance_along(&self, index: usize, direction: &Location) -> f64 {
+        let (width, height) = self.spacing();
+        let column = (index % self.columns) as f64;
+        let row = (index / self.columns) as f64;
+        match direction {
+            Location::East => column * width,
+            Location::West => (self.columns as f64 - 1.0 - column) * width,
+            Location::South => row * height,
+            Location::North => (self.rows as f64 - 1.0 - row) * height,
+            _ => 0.0,
+        }
+    }
+
+    //crossing reached driving from crossing index towards direction, none without a road there
+    pub fn next(&self, index: usize, direction: &Location) -> Option<usize> {
+        self.neighbour(index, direction)
+            .filter(|_| self.has_arm(index, direction))
+    }
+
+    //next crossing on the grid whether or not a road leads there
+    pub fn neighbour(&self, index: usize, direction: &Location) -> Option<usize> {
+        let column = index % self.columns;
+        let row = index / self.columns;
+        match direction {
+            Location::East if column + 1 < self.columns => Some(index + 1),
+            Location::West if column > 0 => Some(index - 1),
+            Location::South if row + 1 < self.rows => Some(index + self.columns),
+            Location::North if row > 0 => Some(index - self.columns),
+            _ => None,
+        }
+    }
+
+    //roads entering the screen on the side of location, one off a corner of a single crossing
+    pub fn roads(&self, location: &Location) -> usize {
+        match location {
+            Location::West | Location::East => self.rows,
+            Location::North | Location::South => self.columns,
+            _ if self.junctions() == 1 => 1,
+            _ => 0,
+        }
+    }
+
+    //first crossing of cars coming in from location on road, counted from the top or the left,
+    //none if it has no arm on that side
+    pub fn entry(&self, location: &Location, road: usize) -> Option<usize> {
+        if road >= self.roads(location) {
+            return None;
+        }
+        let index = match location {
+            Location::West => road * self.columns,
+            Location::East => road * self.columns + self.columns - 1,
+            Location::North => road,
+            Location::South => (self.rows - 1) * self.columns + road,
+            _ => 0,
+        };
+        self.has_arm(index, location).then_some(index)
+    }
+
+    //side and road a car leaves the screen on driving from crossing index towards direction
+    pub fn exit(&self, index: usize, direction: &Location) -> Option<Edge> {
+        if self.neighbour(index, direction).is_some() || !self.has_arm(index, direction) {
+            return None;
+        }
+        let road = match direction {
+            Location::West | Location::East => index / self.columns,
+            Location::North | Location::South => index % self.columns,
+            _ => 0,
+        };
+        Some(Edge {
+            side: direction.clone(),
+            road,
+        })
+    }
+
+    //pixels of road on side of crossing index leading up to its stop lines,
+    //half a road between crossings from the edge of the screen
+    pub fn approach_length(&self, index: usize, side: &Location, layout: &Layout) -> f64 {
+        let inset = layout.half_width() + layout.zebra_width() + layout.bike_box_depth();
+        match self.neighbour(index, side) {
+            Some(_) => self.length(side) - 2.0 * inset,
+            None if self.is_skewed() => {
+                let crossing = self.crossing(index, layout);
+                self.reach(index, crossing.angle(side))
+                    - crossing.edge(side)
+                    - layout.zebra_width()
+                    - layout.bike_box_depth()
+            }
+            None => self.length(side) / 2.0 - inset,
+        }
+    }
+
+    //pixels from the centre of crossing index to the edge of the screen driving at degrees
+    fn reach(&self, index: usize, degrees: f64) -> f64 {
+        let center = self.center(index);
+        let (dx, dy) = Lane::unit(degrees);
+        let to = |from: f64, size: f64, step: f64| match step {
+            0.0 => f64::INFINITY,
+            step if step > 0.0 => (size - from) / step,
+            step => from / -step,
+        };
+        to(center.x, SCREEN_WIDTH_F64, dx).min(to(center.y, SCREEN_HEIGHT_F64, dy))
+    }
+
+    //pixels of road driven from one crossing to the next towards direction
+    fn length(&self, direction: &Location) -> f64 {
+        let (width, height) = self.spacing();
+        match direction {
+            Location::West | Location::East => width,
+            Location::North | Location::South => height,
+            _ => width.hypot(height),
+        }
+    }
+
+    //shortest turns for a car driving towards direction into crossing index to leave on goal,
+    //fewer corners among equally long ways,
+    //the graph is tiny so the nearest open node is found by looking at all of them
+    pub fn route(&self, index: usize, direction: &Location, goal: &Edge) -> Option<Route> {
+        //crossing reached or none once the car has left on goal, heading, pixels and turns so far
+        let mut open = vec![(Some(index), direction.clone(), 0.0_f64, VecDeque::new())];
+        let mut visited = HashSet::new();
+        let corners = |turns: &VecDeque<Destination>| {
+            turns
+                .iter()
+                .filter(|turn| **turn != Destination::Ahead)
+                .count()
+        };
+        while let Some(nearest) = (0..open.len()).min_by(|a, b| {
+            open[*a]
+                .2
+                .total_cmp(&open[*b].2)
+                .then(corners(&open[*a].3).cmp(&corners(&open[*b].3)))
+        }) {
+            let (junction, heading, length, turns) = open.remove(nearest);
+            let Some(junction) = junction else {
+                return Some(Route {
+                    turns,
+                    goal: Some(goal.clone()),
+                });
+            };
+            if !visited.insert((junction, heading.clone())) {
+                continue;
+            }
+            for turn in [
+                Destination::Ahead,
+                Destination::Left,
+                Destination::Right,
+                Destination::BearLeft,
+                Destination::BearRight,
+            ] {
+                let Some(out) = self.turned(junction, &heading, &turn) else {
+                    continue;
+                };
+                let mut turns = turns.clone();
+                turns.push_back(turn);
+                let length = length + self.length(&out);
+                match self.next(junction, &out) {
+                    Some(next) => open.push((Some(next), out, length, turns)),
+                    None if self.exit(junction, &out).as_ref() == Some(goal) => {
+                        open.push((None, out, length, turns))
+                    }
+                    None => {}
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,163 @@
+use crate::constants::*;
+use crate::lane::Lane;
+use crate::model::{Car, Config, Destination, Location, PointF, SignalState, TrafficLight};
+use std::collections::HashMap;
+
+//what the signal at the kerbs of a zebra crossing shows
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum WalkSignal {
+    DontWalk,
+    Walk,
+    //flashing, finish crossing but do not start
+    Clearance,
+    //no lights in charge, people on or waiting at the zebra go first
+    Dark,
+}
+
+impl WalkSignal {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WalkSignal::DontWalk => "don't walk",
+            WalkSignal::Walk => "walk",
+            WalkSignal::Clearance => "clearance",
+            WalkSignal::Dark => "dark",
+        }
+    }
+
+    //signal of the zebra over the arm on side, people walk while the cars crossing it stand,
+    //alongside the green of the traffic beside them or in a pedestrian phase,
+    //and may finish until the cars over the zebra get red amber
+    pub fn of(
+        side: &Location,
+        lights: &HashMap<Location, TrafficLight>,
+        pedestrian_phase: bool,
+        previous: Self,
+    ) -> Self {
+        if lights
+            .values()
+            .any(|light| light.state == SignalState::FlashingAmber)
+        {
+            return WalkSignal::Dark;
+        }
+        let red = |side: &Location| {
+            lights
+                .get(side)
+                .is_none_or(|light| light.state == SignalState::Red)
+        };
+        let alongside = [Destination::Left, Destination::Right]
+            .iter()
+            .filter_map(|turn| lights.get(&side.turned(turn)))
+            .any(|light| light.state == SignalState::Green);
+        let stopped = red(side) && red(&side.opposite());
+        match previous {
+            _ if stopped && (pedestrian_phase || alongside) => WalkSignal::Walk,
+            WalkSignal::Walk | WalkSignal::Clearance if stopped => WalkSignal::Clearance,
+            _ => WalkSignal::DontWalk,
+        }
+    }
+}
+
+//someone crossing one arm of a crossing on its zebra
+#[derive(Clone, Debug)]
+pub struct Pedestrian {
+    pub id: u64,
+    pub junction: usize,
+    //side of the crossing the arm lies on
+    pub arm: Location,
+    //where the pedestrian is across the arm as Crossing::across has it
+    pub cross: f64,
+    //where the pedestrian steps off the road on the far kerb
+    pub target: f64,
+    pub walking: bool,
+    //ticks since spawn
+    pub age: u64,
+    //ticks spent waiting at the kerb
+    pub waiting: u64,
+}
+
+impl Pedestrian {
+    //someone waiting at the kerb of the zebra over arm, on the side with the larger x or y if far
+    pub fn new(junction: usize, arm: Location, far: bool, config: &Config) -> Self {
+        let crossing = &config.crossings[junction];
+        let middle = crossing.across(&arm, &crossing.center);
+        let kerb = Self::kerb(config);
+        let (cross, target) = match far {
+            true => (middle + kerb, middle - kerb),
+            false => (middle - kerb, middle + kerb),
+        };
+        Self {
+            id: 0,
+            junction,
+            arm,
+            cross,
+            target,
+            walking: false,
+            age: 0,
+            waiting: 0,
+        }
+    }
+
+    //pixels from the middle of the road to where people wait,
+    //a step back from the road as cars cutting the corner brush the edge of the kerb
+    pub fn kerb(config: &Config) -> f64 {
+        config.layout.road_half_width() + PEDESTRIAN_SIZE_F64 * 2.0
+    }
+
+    pub fn position(&self, config: &Config) -> PointF {
+        Self::zebra_point(self.junction, &self.arm, self.cross, config)
+    }
+
+    //point on the middle line of the zebra over arm of crossing junction, cross as in Pedestrian::cross
+    pub fn zebra_point(junction: usize, arm: &Location, cross: f64, config: &Config) -> PointF {
+        let crossing = &config.crossings[junction];
+        let (dx, dy) = crossing.heading(arm);
+        let out = crossing.edge(arm) + config.layout.zebra_width() / 2.0;
+        let middle = PointF::new(crossing.center.x + dx * out, crossing.center.y + dy * out);
+        //slide across the arm from the middle of the road, straight arms keep cross exactly
+        let (nx, ny) = crossing.normal(arm);
+        let from = crossing.across(arm, &middle);
+        PointF::new(
+            middle.x - nx * from + nx * cross,
+            middle.y - ny * from + ny * cross,
+        )
+    }
+
+    //stretch across the arm still to walk, the whole way for someone still waiting
+    fn path(&self) -> (f64, f64) {
+        (
+            self.cross.min(self.target) - PEDESTRIAN_SIZE_F64 / 2.0,
+            self.cross.max(self.target) + PEDESTRIAN_SIZE_F64 / 2.0,
+        )
+    }
+
+    //in the way of cars crossing the zebra on lane
+    pub fn blocks(&self, lane: &Lane, config: &Config) -> bool {
+        let (from, to) = self.path();
+        let offset = config.crossings[self.junction].across(&self.arm, &lane.start);
+        from < offset + LANE_WIDTH / 2.0 && to > offset - LANE_WIDTH / 2.0
+    }
+
+    //start on walk or at a dark zebra once no car is on it or too close to stop, then keep going
+    pub fn step(&mut self, signal: WalkSignal, cars: &[Car], config: &Config) {
+        self.age += 1;
+        if !self.walking {
+            let may_go = matches!(signal, WalkSignal::Walk | WalkSignal::Dark);
+            if !may_go || cars.iter().any(|car| car.is_committed_to(self, config)) {
+                self.waiting += 1;
+                return;
+            }
+            self.walking = true;
+        }
+        let step = PEDESTRIAN_SPEED * TICK_DURATION;
+        let left = self.target - self.cross;
+        self.cross += left.clamp(-step, step);
+    }
+
+    pub fn has_crossed(&self) -> bool {
+        self.walking && (self.target - self.cross).abs() < POSITION_TOLERANCE
+    }
+
+    pub fn waiting_time(&self) -> f64 {
+        self.waiting as f64 * TICK_DURATION
+    }
+}
@@ -0,0 +1,410 @@
+use crate::following::CarFollowing;
+use crate::layout::Layout;
+use crate::model::{Destination, Location};
+use crate::network::{Edge, Network, Route};
+use crate::signal::SignalConfig;
+use crate::vehicle::VehicleKind;
+use rand::Rng;
+use serde::Deserialize;
+use std::fs;
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct Scenario {
+    //length of the run in seconds, run forever if not set
+    pub duration: Option<f64>,
+    //random seed, overridden by --seed
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub spawn: Vec<SpawnEvent>,
+    #[serde(default)]
+    pub generator: Vec<Generator>,
+    #[serde(default)]
+    pub signal: SignalConfig,
+    #[serde(default)]
+    pub following: CarFollowing,
+    #[serde(default)]
+    pub layout: Layout,
+    #[serde(default)]
+    pub network: Network,
+    //people arriving at the zebra crossings, needs zebras in the layout
+    pub pedestrians: Option<PedestrianFlow>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SpawnEvent {
+    pub time: f64,
+    pub from: Location,
+    //road on that side counted from the top or the left
+    #[serde(default)]
+    pub road: usize,
+    //either the turn at the first crossing or the side and road to leave the screen on
+    pub turn: Option<Destination>,
+    pub to: Option<Location>,
+    #[serde(default)]
+    pub to_road: usize,
+    #[serde(default)]
+    pub vehicle: VehicleKind,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Generator {
+    pub from: Location,
+    //road on that side counted from the top or the left
+    #[serde(default)]
+    pub road: usize,
+    //random turn for every car if neither is set
+    pub turn: Option<Destination>,
+    pub to: Option<Location>,
+    #[serde(default)]
+    pub to_road: usize,
+    pub arrivals: Arrivals,
+    #[serde(default)]
+    pub vehicle: VehicleKind,
+    #[serde(default)]
+    pub start: f64,
+    pub end: Option<f64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PedestrianFlow {
+    //people per second arriving at each zebra
+    pub arrivals: Arrivals,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Arrivals {
+    //mean number of cars per second
+    Poisson { rate: f64 },
+    //seconds between two cars
+    Fixed { headway: f64 },
+}
+
+impl Scenario {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read scenario {}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("Cannot parse scenario {}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let scenario: Scenario = toml::from_str(text).map_err(|e| e.to_string())?;
+        let pedestrians = scenario.pedestrians.iter().map(|flow| &flow.arrivals);
+        for arrivals in scenario
+            .generator
+            .iter()
+            .map(|g| &g.arrivals)
+            .chain(pedestrians)
+        {
+            match *arrivals {
+                Arrivals::Poisson { rate } if !rate.is_finite() || rate <= 0.0 => {
+                    return Err(format!(
+                        "poisson rate must be positive and finite, got {}",
+                        rate
+                    ));
+                }
+                Arrivals::Fixed { headway } if !headway.is_finite() || headway <= 0.0 => {
+                    return Err(format!(
+                        "fixed headway must be positive and finite, got {}",
+                        headway
+                    ));
+                }
+                _ => {}
+            }
+        }
+        scenario.layout.validate()?;
+        if scenario.pedestrians.is_some() && !scenario.layout.zebras {
+            return Err(String::from(
+                "pedestrians need zebra crossings, set zebras = true under [layout]",
+            ));
+        }
+        scenario.network.validate(&scenario.layout)?;
+        let offsets = scenario.signal.offsets().len();
+        if offsets > scenario.network.junctions() {
+            return Err(format!(
+                "{} offsets for {} crossings",
+                offsets,
+                scenario.network.junctions()
+            ));
+        }
+        //a fixed plan has to give every arm green at some point, roundabouts have no lights
+        if let (SignalConfig::Fixed(plan), false) =
+            (&scenario.signal, scenario.layout.is_roundabout())
+        {
+            for junction in 0..scenario.network.junctions() {
+                if let Some(arm) = scenario
+                    .network
+                    .arms(junction)
+                    .into_iter()
+                    .find(|arm| !plan.phase.iter().any(|phase| phase.green.contains(arm)))
+                {
+                    return Err(format!(
+                        "no phase of the signal plan gives the {} arm of crossing {} green",
+                        arm.name(),
+                        junction
+                    ));
+                }
+            }
+        }
+        let trips = scenario
+            .spawn
+            .iter()
+            .map(|event| {
+                (
+                    &event.from,
+                    event.road,
+                    &event.turn,
+                    &event.to,
+                    event.to_road,
+                )
+            })
+            .chain(
+                scenario
+                    .generator
+                    .iter()
+                    .map(|g| (&g.from, g.road, &g.turn, &g.to, g.to_road)),
+            );
+        for (from, road, turn, to, to_road) in trips {
+            if road >= scenario.network.roads(from) {
+                return Err(format!(
+                    "no road {} coming in from the {}, there are {}",
+                    road,
+                    from.name(),
+                    scenario.network.roads(from)
+                ));
+            }
+            let Some(junction) = scenario.network.entry(from, road) else {
+                return Err(format!(
+                    "no road {} coming in from the {}, its crossing has no arm on that side",
+                    road,
+                    from.name()
+                ));
+            };
+            if let Some(turn) = turn {
+                if !scenario
+                    .network
+                    .turns(junction, &from.opposite())
+                    .contains(turn)
+                {
+                    return Err(format!(
+                        "no arm to turn {} into coming in from the {} on road {}",
+                        turn.name(),
+                        from.name(),
+                        road
+                    ));
+                }
+            }
+            if let Some(to) = to {
+                if turn.is_some() {
+                    return Err(String::from(
+                        "a car takes either a turn or a route to a side",
+                    ));
+                }
+                if scenario.route(from, road, to, to_road).is_none() {
+                    return Err(format!(
+                        "no way from road {} in the {} to road {} in the {}",
+                        road,
+                        from.name(),
+                        to_road,
+                        to.name()
+                    ));
+                }
+            }
+        }
+        if let Some(event) = scenario
+            .spawn
+            .iter()
+            .find(|event| event.turn.is_none() && event.to.is_none())
+        {
+            return Err(format!(
+                "car at {} s needs a turn or a side to leave on",
+                event.time
+            ));
+        }
+        Ok(scenario)
+    }
+
+    //shortest way through the network from road on side from to to_road on side to
+    fn route(&self, from: &Location, road: usize, to: &Location, to_road: usize) -> Option<Route> {
+        let junction = self.network.entry(from, road)?;
+        let goal = Edge {
+            side: to.clone(),
+            road: to_road,
+        };
+        self.network.route(junction, &from.opposite(), &goal)
+    }
+
+    //route of a car with turn or goal, random turn if it has neither
+    fn plan(
+        &self,
+        from: &Location,
+        road: usize,
+        turn: &Option<Destination>,
+        to: &Option<Location>,
+        to_road: usize,
+        rng: &mut impl Rng,
+    ) -> Route {
+        if let Some(route) = to
+            .as_ref()
+            .and_then(|to| self.route(from, road, to, to_road))
+        {
+            return route;
+        }
+        match (turn, self.network.entry(from, road)) {
+            (Some(turn), _) => Route::turn(turn.clone()),
+            (None, Some(junction)) => {
+                Route::turn(self.network.random_turn(junction, &from.opposite(), rng))
+            }
+            (None, None) => Route::turn(Destination::get_random(rng)),
+        }
+    }
+}
+
+impl Arrivals {
+    //mean number of cars per second
+    pub fn rate(&self) -> f64 {
+        match self {
+            Arrivals::Poisson { rate } => *rate,
+            Arrivals::Fixed { headway } => 1.0 / headway,
+        }
+    }
+
+    //seconds until the next arrival
+    pub fn next_gap(&self, rng: &mut impl Rng) -> f64 {
+        match self {
+            Arrivals::Poisson { rate } => {
+                let u: f64 = rng.random_range(f64::EPSILON..1.0);
+                -u.ln() / rate
+            }
+            Arrivals::Fixed { headway } => *headway,
+        }
+    }
+}
+
+//turns scenario into spawn requests as simulation time goes by
+pub struct ScenarioRunner {
+    pub scenario: Scenario,
+    events: Vec<SpawnEvent>,
+    next_arrivals: Vec<f64>,
+    //next person to arrive at each zebra as crossing, arm and time
+    next_pedestrians: Vec<(usize, Location, f64)>,
+    //vehicles which could not spawn yet because the entry was blocked
+    pending: Vec<(Edge, Route, VehicleKind)>,
+}
+
+impl ScenarioRunner {
+    pub fn new(scenario: Scenario, rng: &mut impl Rng) -> Self {
+        let mut events = scenario.spawn.clone();
+        //latest event last, so due events can be popped
+        events.sort_by(|a, b| b.time.total_cmp(&a.time));
+        let next_arrivals = scenario
+            .generator
+            .iter()
+            .map(|g| g.start + g.arrivals.next_gap(rng))
+            .collect();
+        let mut next_pedestrians = vec![];
+        if let Some(flow) = &scenario.pedestrians {
+            for junction in 0..scenario.network.junctions() {
+                for arm in scenario.network.arms(junction) {
+                    next_pedestrians.push((junction, arm, flow.arrivals.next_gap(rng)));
+                }
+            }
+        }
+        Self {
+            scenario,
+            events,
+            next_arrivals,
+            next_pedestrians,
+            pending: vec![],
+        }
+    }
+
+    //vehicles that should enter the network at time (seconds)
+    pub fn due(&mut self, time: f64, rng: &mut impl Rng) -> Vec<(Edge, Route, VehicleKind)> {
+        let mut due = std::mem::take(&mut self.pending);
+        while let Some(event) = self.events.last() {
+            if event.time > time {
+                break;
+            }
+            let route = self.scenario.plan(
+                &event.from,
+                event.road,
+                &event.turn,
+                &event.to,
+                event.to_road,
+                rng,
+            );
+            due.push((Self::entry(&event.from, event.road), route, event.vehicle));
+            self.events.pop();
+        }
+        for (index, generator) in self.scenario.generator.iter().enumerate() {
+            while self.next_arrivals[index] <= time {
+                if generator
+                    .end
+                    .is_some_and(|end| self.next_arrivals[index] > end)
+                {
+                    self.next_arrivals[index] = f64::INFINITY;
+                    break;
+                }
+                let route = self.scenario.plan(
+                    &generator.from,
+                    generator.road,
+                    &generator.turn,
+                    &generator.to,
+                    generator.to_road,
+                    rng,
+                );
+                due.push((
+                    Self::entry(&generator.from, generator.road),
+                    route,
+                    generator.vehicle,
+                ));
+                self.next_arrivals[index] += generator.arrivals.next_gap(rng);
+            }
+        }
+        due
+    }
+
+    //zebras someone arrives at by time (seconds) as crossing and arm
+    pub fn due_pedestrians(&mut self, time: f64, rng: &mut impl Rng) -> Vec<(usize, Location)> {
+        let mut due = vec![];
+        let Some(flow) = &self.scenario.pedestrians else {
+            return due;
+        };
+        for (junction, arm, next) in &mut self.next_pedestrians {
+            while *next <= time {
+                due.push((*junction, arm.clone()));
+                *next += flow.arrivals.next_gap(rng);
+            }
+        }
+        due
+    }
+
+    fn entry(from: &Location, road: usize) -> Edge {
+        Edge {
+            side: from.clone(),
+            road,
+        }
+    }
+
+    pub fn postpone(&mut self, entry: Edge, route: Route, kind: VehicleKind) {
+        self.pending.push((entry, route, kind));
+    }
+
+    //cars per second from the generators running at time
+    pub fn spawn_rate(&self, time: f64) -> f64 {
+        self.scenario
+            .generator
+            .iter()
+            .filter(|g| g.start <= time && g.end.is_none_or(|end| time <= end))
+            .map(|g| g.arrivals.rate())
+            .sum()
+    }
+
+    pub fn is_finished(&self, time: f64) -> bool {
+        match self.scenario.duration {
+            Some(duration) => time >= duration,
+            None => false,
+        }
+    }
+}
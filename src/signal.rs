@@ -0,0 +1,809 @@
+use crate::constants::*;
+use crate::model::{Car, Destination, Location, Model, SignalState};
+use crate::network::Network;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+//decides every tick which approaches should get green
+pub trait TrafficController {
+    fn name(&self) -> &'static str;
+    fn decide(&mut self, observation: &Observation) -> Decision;
+}
+
+//what a controller can see of its crossing
+pub struct Observation {
+    pub tick: u64,
+    pub junction: usize,
+    //cars on the way to each stop line
+    pub approaching: HashMap<Location, usize>,
+    //cars standing in front of each stop line
+    pub queues: HashMap<Location, usize>,
+    //cars that fit standing on each approach
+    pub capacity: HashMap<Location, usize>,
+    //seconds the cars on each approach have spent standing so far
+    pub waiting_times: HashMap<Location, f64>,
+    //approaches where a car has just reached a detector
+    pub detections: Vec<Location>,
+    pub crossing_clear: bool,
+    pub lights: HashMap<Location, SignalState>,
+    //seconds since each light last changed
+    pub light_times: HashMap<Location, f64>,
+    //seconds the signal plan of the crossing runs behind the common cycle
+    pub offset: f64,
+    //seconds the first person waiting at the zebra on each arm has waited, none if nobody waits
+    pub pedestrians: HashMap<Location, f64>,
+    //approaches an emergency vehicle calls green for, nearest to its stop line first
+    pub emergencies: Vec<Location>,
+}
+
+impl Observation {
+    pub fn new(model: &Model, junction: usize, tick: u64) -> Self {
+        let mut approaching = HashMap::new();
+        let mut queues = HashMap::new();
+        let mut waiting_times = HashMap::new();
+        for location in Location::compass() {
+            approaching.insert(location.clone(), 0);
+            queues.insert(location.clone(), 0);
+            waiting_times.insert(location, 0.0);
+        }
+        let mut detections = vec![];
+        let mut calls = vec![];
+        let roundabout = model.config.layout.is_roundabout();
+        for car in model
+            .cars
+            .iter()
+            .filter(|car| car.junction == Some(junction))
+        {
+            if let Some(location) = car.approach() {
+                *approaching.get_mut(&location).unwrap() += 1;
+                if car.is_stopped {
+                    *queues.get_mut(&location).unwrap() += 1;
+                }
+                *waiting_times.get_mut(&location).unwrap() += car.waiting_time();
+            }
+            if let Some(location) = Self::detect(car) {
+                detections.push(location);
+            }
+            //roundabouts have no lights to call
+            if let (false, Some(distance)) = (roundabout, car.preemption_call()) {
+                calls.push((distance, car.origin.clone()));
+            }
+        }
+        let mut pedestrians = HashMap::new();
+        for pedestrian in model
+            .pedestrians
+            .iter()
+            .filter(|pedestrian| pedestrian.junction == junction && !pedestrian.walking)
+        {
+            let wait = pedestrians.entry(pedestrian.arm.clone()).or_insert(0.0);
+            *wait = pedestrian.waiting_time().max(*wait);
+        }
+        //nearest call first
+        calls.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut emergencies: Vec<Location> = vec![];
+        for (_, location) in calls {
+            if !emergencies.contains(&location) {
+                emergencies.push(location);
+            }
+        }
+        let config = &model.config;
+        let spot = CAR_SIZE_F64 + config.following.standstill_gap();
+        let capacity = Location::compass()
+            .into_iter()
+            .map(|location| {
+                let length = config
+                    .network
+                    .approach_length(junction, &location, &config.layout);
+                let cars = (length / spot).floor().max(0.0) as usize;
+                (location, cars * config.layout.lanes.len())
+            })
+            .collect();
+        let switch = &model.junctions[junction].traffic_light_switch;
+        let traffic_lights = &switch.traffic_lights;
+        let lights = traffic_lights
+            .values()
+            .map(|light| (light.location.clone(), light.state))
+            .collect();
+        let light_times = traffic_lights
+            .values()
+            .map(|light| (light.location.clone(), light.elapsed_time()))
+            .collect();
+        Self {
+            tick,
+            junction,
+            approaching,
+            queues,
+            capacity,
+            waiting_times,
+            detections,
+            crossing_clear: Model::is_crossing_clear(&model.cars, junction, &model.config),
+            lights,
+            light_times,
+            offset: switch.offset,
+            pedestrians,
+            emergencies,
+        }
+    }
+
+    //car driving onto one of the detectors of its approach lane this tick,
+    //detectors are DETECTOR_LENGTH long so a car stopping just short of one still counts
+    pub fn detect(car: &Car) -> Option<Location> {
+        car.stop_line_distance()?;
+        let after = car.lane.along(&car.position);
+        let before = after - car.speed;
+        car.detectors()
+            .iter()
+            .map(|detector| detector - DETECTOR_LENGTH)
+            .any(|start| before < start && start <= after)
+            .then(|| car.origin.clone())
+    }
+
+    pub fn time(&self) -> f64 {
+        self.tick as f64 * TICK_DURATION
+    }
+
+    //flat numbers for learning agents, four values per approach in Location::all order
+    //then 1.0 if the crossing is clear
+    pub fn features(&self) -> Vec<f64> {
+        let mut features = vec![];
+        for location in Location::all() {
+            features.push(self.approaching[&location] as f64);
+            features.push(self.queues[&location] as f64);
+            features.push(self.waiting_times[&location]);
+            //a side without an arm has no light and counts as red
+            let green = matches!(
+                self.lights.get(&location),
+                Some(SignalState::Green | SignalState::RedAmber)
+            );
+            features.push(if green { 1.0 } else { 0.0 });
+        }
+        features.push(if self.crossing_clear { 1.0 } else { 0.0 });
+        features
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Decision {
+    //leave lights as they are
+    Keep,
+    //green for these approaches once the crossing is clear
+    Request(Vec<Location>),
+    //stop every other approach now, then green for these
+    Force(Vec<Location>),
+    //all lights flashing amber
+    Flashing,
+    //every light red, then people walk on every zebra
+    Walk,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "controller", rename_all = "lowercase")]
+pub enum SignalConfig {
+    Actuated(SignalTiming),
+    Fixed(SignalPlan),
+    #[serde(rename = "max-pressure")]
+    MaxPressure(MaxPressureConfig),
+    Flashing,
+}
+
+impl Default for SignalConfig {
+    fn default() -> Self {
+        SignalConfig::Actuated(SignalTiming::default())
+    }
+}
+
+impl SignalConfig {
+    pub fn build(&self) -> Result<Box<dyn TrafficController>, String> {
+        self.timing().validate()?;
+        Ok(match self {
+            SignalConfig::Actuated(timing) => Box::new(ActuatedController::new(timing)),
+            SignalConfig::Fixed(plan) => Box::new(FixedTimeController::new(plan.clone())?),
+            SignalConfig::MaxPressure(config) => Box::new(MaxPressureController::new(config)?),
+            SignalConfig::Flashing => Box::new(FlashingController),
+        })
+    }
+
+    //offsets of the crossings in network order, only fixed plans have them
+    pub fn offsets(&self) -> Vec<f64> {
+        match self {
+            SignalConfig::Fixed(plan) => plan.offsets.clone(),
+            _ => vec![],
+        }
+    }
+
+    //clearance times the light switch has to keep
+    pub fn timing(&self) -> SignalTiming {
+        match self {
+            SignalConfig::Actuated(timing) => timing.clone(),
+            SignalConfig::Fixed(plan) => plan.timing(),
+            SignalConfig::MaxPressure(config) => config.timing.clone(),
+            SignalConfig::Flashing => SignalTiming::default(),
+        }
+    }
+}
+
+//seconds every light spends in its transient states and the bounds of a green
+//for controllers that pick its length
+#[derive(Deserialize, Debug, Clone)]
+pub struct SignalTiming {
+    #[serde(default = "SignalPlan::default_amber")]
+    pub amber: f64,
+    #[serde(default = "SignalPlan::default_red_amber")]
+    pub red_amber: f64,
+    #[serde(default = "SignalPlan::default_all_red")]
+    pub all_red: f64,
+    #[serde(default = "SignalTiming::default_min_green")]
+    pub min_green: f64,
+    //a green runs no longer than this while another phase waits for it
+    #[serde(default = "SignalTiming::default_max_green")]
+    pub max_green: f64,
+}
+
+impl Default for SignalTiming {
+    fn default() -> Self {
+        Self {
+            amber: DEFAULT_AMBER_TIME,
+            red_amber: DEFAULT_RED_AMBER_TIME,
+            all_red: DEFAULT_ALL_RED_TIME,
+            min_green: DEFAULT_MIN_GREEN_TIME,
+            max_green: DEFAULT_MAX_GREEN_TIME,
+        }
+    }
+}
+
+impl SignalTiming {
+    fn default_min_green() -> f64 {
+        DEFAULT_MIN_GREEN_TIME
+    }
+
+    fn default_max_green() -> f64 {
+        DEFAULT_MAX_GREEN_TIME
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let times = [
+            self.amber,
+            self.red_amber,
+            self.all_red,
+            self.min_green,
+            self.max_green,
+        ];
+        if times.iter().any(|time| !time.is_finite()) {
+            return Err(String::from("signal times must be finite"));
+        }
+        if self.amber < 0.0 || self.red_amber < 0.0 || self.all_red < 0.0 {
+            return Err(String::from(
+                "amber, red amber and all red times cannot be negative",
+            ));
+        }
+        if self.min_green < 0.0 || self.max_green < self.min_green {
+            return Err(String::from(
+                "minimum green cannot be negative or longer than the maximum green",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SignalPlan {
+    #[serde(default = "SignalPlan::default_phases")]
+    pub phase: Vec<Phase>,
+    //seconds of amber after every green
+    #[serde(default = "SignalPlan::default_amber")]
+    pub amber: f64,
+    //seconds with all lights red after amber
+    #[serde(default = "SignalPlan::default_all_red")]
+    pub all_red: f64,
+    //seconds of red amber at the start of every green
+    #[serde(default = "SignalPlan::default_red_amber")]
+    pub red_amber: f64,
+    //green times are stretched to fill the cycle if set
+    pub cycle: Option<f64>,
+    //seconds each crossing in network order starts its cycle later, the rest start with the first
+    #[serde(default)]
+    pub offsets: Vec<f64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Phase {
+    //none for a pedestrian phase
+    pub green: Vec<Location>,
+    //seconds of green
+    pub duration: f64,
+}
+
+impl Default for SignalPlan {
+    fn default() -> Self {
+        Self {
+            phase: Self::default_phases(),
+            amber: Self::default_amber(),
+            all_red: Self::default_all_red(),
+            red_amber: Self::default_red_amber(),
+            cycle: None,
+            offsets: vec![],
+        }
+    }
+}
+
+impl SignalPlan {
+    fn default_phases() -> Vec<Phase> {
+        [Location::West, Location::North]
+            .iter()
+            .map(|location| Phase {
+                green: phase_of(location),
+                duration: DEFAULT_GREEN_TIME,
+            })
+            .collect()
+    }
+
+    //default plan over the arms of network, each arm gets green together with the opposite one
+    pub fn for_network(network: &Network) -> Self {
+        let arms: Vec<Location> = (0..network.junctions())
+            .flat_map(|junction| network.arms(junction))
+            .collect();
+        let mut phase: Vec<Phase> = vec![];
+        for arm in &arms {
+            if phase.iter().any(|phase| phase.green.contains(arm)) {
+                continue;
+            }
+            phase.push(Phase {
+                green: phase_of(arm)
+                    .into_iter()
+                    .filter(|side| arms.contains(side))
+                    .collect(),
+                duration: DEFAULT_GREEN_TIME,
+            });
+        }
+        Self {
+            phase,
+            ..Self::default()
+        }
+    }
+
+    fn default_amber() -> f64 {
+        DEFAULT_AMBER_TIME
+    }
+
+    fn default_all_red() -> f64 {
+        DEFAULT_ALL_RED_TIME
+    }
+
+    fn default_red_amber() -> f64 {
+        DEFAULT_RED_AMBER_TIME
+    }
+
+    pub fn cycle_length(&self) -> f64 {
+        self.phase
+            .iter()
+            .map(|phase| phase.duration + self.amber + self.all_red)
+            .sum()
+    }
+
+    pub fn timing(&self) -> SignalTiming {
+        SignalTiming {
+            amber: self.amber,
+            red_amber: self.red_amber,
+            all_red: self.all_red,
+            ..SignalTiming::default()
+        }
+    }
+
+    //check plan and fit green times into the cycle length
+    pub fn normalize(mut self) -> Result<Self, String> {
+        if self.phase.is_empty() {
+            return Err(String::from("signal plan needs at least one phase"));
+        }
+        self.timing().validate()?;
+        if self
+            .phase
+            .iter()
+            .any(|phase| !phase.duration.is_finite() || phase.duration <= 0.0)
+        {
+            return Err(String::from(
+                "green time of every phase must be positive and finite",
+            ));
+        }
+        if let Some(cycle) = self.cycle {
+            if !cycle.is_finite() {
+                return Err(format!("cycle of {} s must be finite", cycle));
+            }
+            let lost_time = self.phase.len() as f64 * (self.amber + self.all_red);
+            if cycle <= lost_time {
+                return Err(format!(
+                    "cycle of {} s leaves no green time after {} s of clearance",
+                    cycle, lost_time
+                ));
+            }
+            let green_time: f64 = self.phase.iter().map(|phase| phase.duration).sum();
+            let scale = (cycle - lost_time) / green_time;
+            for phase in &mut self.phase {
+                phase.duration *= scale;
+            }
+        }
+        if self
+            .phase
+            .iter()
+            .any(|phase| phase.duration <= self.red_amber)
+        {
+            return Err(String::from(
+                "green time of every phase must exceed red amber",
+            ));
+        }
+        let cycle = self.cycle_length();
+        if let Some(offset) = self
+            .offsets
+            .iter()
+            .find(|offset| !offset.is_finite() || !(0.0..cycle).contains(*offset))
+        {
+            return Err(format!(
+                "offset of {} s must lie within the {} s cycle",
+                offset, cycle
+            ));
+        }
+        Ok(self)
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Interval {
+    RedAmber,
+    Green,
+    Amber,
+    AllRed,
+}
+
+//offsets that let cars leaving the first crossing of a corridor as its green starts find green
+//at every further crossing, distances in pixels from the first one and speed in pixels per second
+pub fn green_wave(distances: &[f64], speed: f64, cycle: f64) -> Vec<f64> {
+    distances
+        .iter()
+        .map(|distance| (distance / speed).rem_euclid(cycle))
+        .collect()
+}
+
+//opposing approaches share a green, their turns across each other yield
+pub fn phase_of(location: &Location) -> Vec<Location> {
+    vec![location.clone(), location.opposite()]
+}
+
+//request driven lights: phases get green in the order their cars reach a detector,
+//a green is extended while its own cars keep coming up to the maximum green,
+//a long queue forces green for its approach and the opposite one
+pub struct ActuatedController {
+    pub request: Option<Location>,
+    //request came from a long queue or waited out a maximum green
+    pub urgent: bool,
+    //approaches called by a detector and not shown green yet with the time of the call,
+    //oldest first and one per phase
+    pub pending: Vec<(Location, f64)>,
+    //time a car last reached a detector on the requested phase
+    pub last_detection: f64,
+    pub min_green: f64,
+    pub max_green: f64,
+}
+
+impl Default for ActuatedController {
+    fn default() -> Self {
+        Self::new(&SignalTiming::default())
+    }
+}
+
+impl TrafficController for ActuatedController {
+    fn name(&self) -> &'static str {
+        "actuated"
+    }
+
+    fn decide(&mut self, observation: &Observation) -> Decision {
+        let time = observation.time();
+        //a call is answered once its approach shows green, cars waiting in a queue
+        //do not reach a detector again
+        self.pending
+            .retain(|(location, _)| observation.lights[location] != SignalState::Green);
+        for location in &observation.detections {
+            if self
+                .request
+                .as_ref()
+                .is_some_and(|request| phase_of(request).contains(location))
+            {
+                self.last_detection = time;
+            }
+            self.call(location, observation);
+        }
+        //people waiting at a zebra call the green they can walk beside when no car does
+        if self.pending.is_empty() {
+            if let Some(beside) = Self::walk_beside(observation, 0.0) {
+                self.call(&beside, observation);
+            }
+        }
+        //let the requested approach get green and keep it for the minimum green time,
+        //otherwise two long queues take the crossing from each other during clearance
+        if let Some(location) = &self.request {
+            let state = observation.lights[location];
+            let starting = state == SignalState::RedAmber
+                || (state == SignalState::Green
+                    && observation.light_times[location] < self.min_green);
+            if starting || (self.urgent && state != SignalState::Green) {
+                return self.decision();
+            }
+        }
+        self.urgent = false;
+        //a call that has waited out a maximum green goes before long queues
+        let overdue = self
+            .pending
+            .iter()
+            .find(|(_, called)| time - called >= self.max_green);
+        if let Some((location, _)) = overdue {
+            self.serve(location.clone(), true);
+            return self.decision();
+        }
+        //a long queue takes the green from a phase without one, two long queues
+        //take turns through the maximum green
+        let long = |location: &Location| {
+            let cars = observation.approaching.get(location).copied().unwrap_or(0);
+            let capacity = observation.capacity[location] as f64;
+            cars > 0 && cars as f64 >= (capacity * LONG_QUEUE_SHARE).ceil()
+        };
+        let green_is_long = self.request.as_ref().is_some_and(|request| {
+            observation.lights[request] == SignalState::Green && phase_of(request).iter().any(long)
+        });
+        for location in [
+            Location::West,
+            Location::East,
+            Location::North,
+            Location::South,
+            Location::NorthWest,
+            Location::SouthEast,
+            Location::NorthEast,
+            Location::SouthWest,
+        ] {
+            if !green_is_long
+                && long(&location)
+                && observation.lights[&location] != SignalState::Green
+            {
+                self.serve(location, true);
+                return self.decision();
+            }
+        }
+        //people waiting long at a zebra stop the cars as a long queue does
+        if let Some(beside) = Self::walk_beside(observation, MAX_PEDESTRIAN_WAIT) {
+            if observation.lights[&beside] != SignalState::Green {
+                self.serve(beside, true);
+                return self.decision();
+            }
+        }
+        //the green phase keeps the lights while its cars keep coming, but only up to
+        //the maximum green if another phase waits
+        let next = self.pending.first().map(|(location, _)| location.clone());
+        let green = self
+            .request
+            .as_ref()
+            .filter(|location| observation.lights[*location] == SignalState::Green);
+        match (green, next) {
+            (Some(location), Some(next)) => {
+                //cars standing on green wait for a gap to turn and still need it
+                let extended = time - self.last_detection < GAP_OUT_TIME
+                    || phase_of(location)
+                        .iter()
+                        .any(|approach| observation.queues[approach] > 0);
+                let maxed_out = observation.light_times[location] >= self.max_green;
+                if maxed_out || !extended {
+                    self.serve(next, maxed_out);
+                }
+            }
+            (None, Some(next)) if self.request.is_none() => self.serve(next, false),
+            _ => {}
+        }
+        self.decision()
+    }
+}
+
+impl ActuatedController {
+    pub fn new(timing: &SignalTiming) -> Self {
+        Self {
+            request: None,
+            urgent: false,
+            pending: vec![],
+            last_detection: 0.0,
+            min_green: timing.min_green,
+            max_green: timing.max_green,
+        }
+    }
+
+    //remember that location wants green unless it has it or its phase already waits for it
+    fn call(&mut self, location: &Location, observation: &Observation) {
+        let phase = phase_of(location);
+        let waiting = self
+            .pending
+            .iter()
+            .any(|(pending, _)| phase.contains(pending));
+        if observation.lights[location] != SignalState::Green && !waiting {
+            self.pending.push((location.clone(), observation.time()));
+        }
+    }
+
+    fn serve(&mut self, location: Location, urgent: bool) {
+        self.request = Some(location);
+        self.urgent = urgent;
+    }
+
+    //approach whose green lets someone who has waited at least wait seconds walk,
+    //people walk beside the traffic of the arms next to theirs
+    fn walk_beside(observation: &Observation, wait: f64) -> Option<Location> {
+        Location::compass()
+            .into_iter()
+            .filter(|arm| observation.pedestrians.get(arm).is_some_and(|w| *w >= wait))
+            .flat_map(|arm| {
+                [
+                    arm.turned(&Destination::Left),
+                    arm.turned(&Destination::Right),
+                ]
+            })
+            .find(|beside| observation.lights.contains_key(beside))
+    }
+
+    fn decision(&self) -> Decision {
+        match &self.request {
+            Some(location) if self.urgent => Decision::Force(phase_of(location)),
+            Some(location) => Decision::Request(phase_of(location)),
+            None => Decision::Keep,
+        }
+    }
+}
+
+//classic fixed time plan cycling through its phases
+pub struct FixedTimeController {
+    pub plan: SignalPlan,
+    pub phase: usize,
+    pub interval: Interval,
+}
+
+impl FixedTimeController {
+    pub fn new(plan: SignalPlan) -> Result<Self, String> {
+        Ok(Self {
+            plan: plan.normalize()?,
+            phase: 0,
+            interval: Interval::Green,
+        })
+    }
+
+    //phase and interval running at time (seconds) of the crossing's own cycle
+    pub fn state_at(&self, time: f64) -> (usize, Interval) {
+        let time_in_cycle = time.rem_euclid(self.plan.cycle_length());
+        let mut start = 0.0;
+        for (index, phase) in self.plan.phase.iter().enumerate() {
+            let green_end = start + phase.duration;
+            let amber_end = green_end + self.plan.amber;
+            let end = amber_end + self.plan.all_red;
+            if time_in_cycle < start + self.plan.red_amber {
+                return (index, Interval::RedAmber);
+            }
+            if time_in_cycle < green_end {
+                return (index, Interval::Green);
+            }
+            if time_in_cycle < amber_end {
+                return (index, Interval::Amber);
+            }
+            if time_in_cycle < end {
+                return (index, Interval::AllRed);
+            }
+            start = end;
+        }
+        //rounding at the very end of the cycle
+        (self.plan.phase.len() - 1, Interval::AllRed)
+    }
+}
+
+impl TrafficController for FixedTimeController {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    fn decide(&mut self, observation: &Observation) -> Decision {
+        let (phase, interval) = self.state_at(observation.time() - observation.offset);
+        self.phase = phase;
+        self.interval = interval;
+        let walk = self.plan.phase[phase].green.is_empty();
+        match interval {
+            Interval::RedAmber | Interval::Green if walk => Decision::Walk,
+            Interval::RedAmber | Interval::Green => {
+                Decision::Force(self.plan.phase[phase].green.clone())
+            }
+            Interval::Amber | Interval::AllRed => Decision::Force(vec![]),
+        }
+    }
+}
+
+//serves the pair of opposing approaches with the longest queue, total waiting time breaks ties
+pub struct MaxPressureController {
+    //seconds a green is kept before queues are compared again
+    pub min_green: f64,
+    //west for west and east, north for north and south
+    pub current: Option<Location>,
+    pub switched_at: f64,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct MaxPressureConfig {
+    //min_green is how long a green is kept before queues are compared again
+    #[serde(flatten)]
+    pub timing: SignalTiming,
+}
+
+impl MaxPressureController {
+    pub fn new(config: &MaxPressureConfig) -> Result<Self, String> {
+        config.timing.validate()?;
+        Ok(Self {
+            min_green: config.timing.min_green,
+            current: None,
+            switched_at: 0.0,
+        })
+    }
+
+    //longest queue, then longest wait, then most cars on the way
+    fn pressure(observation: &Observation, location: &Location) -> (usize, f64, usize) {
+        let phase = phase_of(location);
+        (
+            phase.iter().map(|l| observation.queues[l]).sum(),
+            phase.iter().map(|l| observation.waiting_times[l]).sum(),
+            phase.iter().map(|l| observation.approaching[l]).sum(),
+        )
+    }
+}
+
+impl TrafficController for MaxPressureController {
+    fn name(&self) -> &'static str {
+        "max-pressure"
+    }
+
+    fn decide(&mut self, observation: &Observation) -> Decision {
+        let time = observation.time();
+        if self.current.is_some() && time - self.switched_at < self.min_green {
+            return Decision::Keep;
+        }
+        //one location stands for each pair of opposing approaches
+        let best = [
+            Location::West,
+            Location::North,
+            Location::NorthWest,
+            Location::NorthEast,
+        ]
+        .into_iter()
+        .filter(|location| Self::pressure(observation, location).2 > 0)
+        .max_by(|a, b| {
+            let (queue_a, wait_a, cars_a) = Self::pressure(observation, a);
+            let (queue_b, wait_b, cars_b) = Self::pressure(observation, b);
+            queue_a
+                .cmp(&queue_b)
+                .then(wait_a.total_cmp(&wait_b))
+                .then(cars_a.cmp(&cars_b))
+        });
+        let Some(best) = best else {
+            return Decision::Keep;
+        };
+        if self.current.as_ref() != Some(&best) {
+            //keep current green while it has the same pressure
+            if let Some(current) = &self.current {
+                if Self::pressure(observation, current) == Self::pressure(observation, &best) {
+                    return Decision::Keep;
+                }
+            }
+            self.current = Some(best.clone());
+            self.switched_at = time;
+        }
+        Decision::Force(phase_of(&best))
+    }
+}
+
+//all lights flashing amber, drivers give way to cars in the crossing
+pub struct FlashingController;
+
+impl TrafficController for FlashingController {
+    fn name(&self) -> &'static str {
+        "flashing"
+    }
+
+    fn decide(&mut self, _observation: &Observation) -> Decision {
+        Decision::Flashing
+    }
+}
@@ -0,0 +1,274 @@
+use crate::collision::{Collision, Obb};
+use crate::constants::*;
+use crate::metrics::{Kpis, Trip};
+use crate::model::{Car, Model};
+use crate::scenario::{Scenario, ScenarioRunner};
+use crate::signal::{ActuatedController, Decision, Observation, TrafficController};
+use rand::Rng;
+use std::collections::HashSet;
+
+pub struct Simulation {
+    pub model: Model,
+    pub tick: u64,
+    pub scenario: Option<ScenarioRunner>,
+    //one controller for every crossing
+    pub traffic_controllers: Vec<Box<dyn TrafficController>>,
+    //cars that have left the screen
+    pub throughput: u64,
+    pub trips: Vec<Trip>,
+    pub kpis: Kpis,
+    pub collisions: Vec<Collision>,
+    //pairs of car ids overlapping right now
+    pub colliding: HashSet<(u64, u64)>,
+    //stop stepping when cars collide
+    pub pause_on_collision: bool,
+    pub paused: bool,
+    accumulator: f64,
+}
+
+impl Simulation {
+    pub fn new(model: Model) -> Self {
+        let traffic_controllers = Self::default_controllers(&model);
+        Self {
+            model,
+            tick: 0,
+            scenario: None,
+            traffic_controllers,
+            throughput: 0,
+            trips: vec![],
+            kpis: Kpis::default(),
+            collisions: vec![],
+            colliding: HashSet::new(),
+            pause_on_collision: false,
+            paused: false,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn with_scenario(mut model: Model, scenario: Scenario) -> Self {
+        model.set_roads(scenario.layout.clone(), scenario.network.clone());
+        model.config.following = scenario.following.clone();
+        Self::on_roads(model, scenario)
+    }
+
+    //model already on the roads of scenario with its car following
+    pub fn on_roads(model: Model, scenario: Scenario) -> Self {
+        let mut simulation = Self::new(model);
+        let runner = ScenarioRunner::new(scenario, &mut simulation.model.rng);
+        simulation.scenario = Some(runner);
+        simulation
+    }
+
+    fn default_controllers(model: &Model) -> Vec<Box<dyn TrafficController>> {
+        model
+            .junctions
+            .iter()
+            .map(|_| Box::<ActuatedController>::default() as Box<dyn TrafficController>)
+            .collect()
+    }
+
+    //simulated time in seconds
+    pub fn time(&self) -> f64 {
+        self.tick as f64 * TICK_DURATION
+    }
+
+    //cars per second the scenario generators currently spawn
+    pub fn spawn_rate(&self) -> f64 {
+        match &self.scenario {
+            Some(runner) => runner.spawn_rate(self.time()),
+            None => 0.0,
+        }
+    }
+
+    pub fn has_duration(&self) -> bool {
+        self.scenario
+            .as_ref()
+            .is_some_and(|runner| runner.scenario.duration.is_some())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match &self.scenario {
+            Some(runner) => runner.is_finished(self.time()),
+            None => false,
+        }
+    }
+
+    //advance simulation by dt seconds in fixed ticks
+    pub fn step(&mut self, dt: f64) {
+        if self.paused {
+            return;
+        }
+        self.accumulator += dt;
+        while self.accumulator >= TICK_DURATION && !self.paused {
+            self.advance();
+            self.accumulator -= TICK_DURATION;
+        }
+    }
+
+    //advance simulation by exactly one tick
+    pub fn advance(&mut self) {
+        let observations = self.move_cars();
+        let decisions: Vec<Decision> = self
+            .traffic_controllers
+            .iter_mut()
+            .zip(&observations)
+            .map(|(controller, observation)| controller.decide(observation))
+            .collect();
+        self.switch_lights(&decisions, &observations);
+    }
+
+    //advance one tick with the lights of every crossing following decision instead of the controllers,
+    //observations come in crossing order
+    pub fn advance_with(&mut self, decision: &Decision) -> Vec<Observation> {
+        let observations = self.move_cars();
+        let decisions = vec![decision.clone(); observations.len()];
+        self.switch_lights(&decisions, &observations);
+        observations
+    }
+
+    fn move_cars(&mut self) -> Vec<Observation> {
+        self.spawn_scenario_cars();
+        let all_cars = self.model.cars.clone();
+        for car in &mut self.model.cars {
+            let distance = car.distance;
+            let was_stopped = car.is_stopped;
+            car.drive(
+                &all_cars,
+                &self.model.pedestrians,
+                &self.model.junctions,
+                &self.model.config,
+            );
+            car.is_stopped = car.distance - distance < POSITION_TOLERANCE;
+            if car.is_stopped && !was_stopped {
+                car.stops += 1;
+            }
+            car.age += 1;
+            if car.is_stopped {
+                car.waiting += 1;
+                if car.approach().is_some() {
+                    car.queue_ticks += 1;
+                }
+            }
+            if car.is_in_crossing(&self.model.config) {
+                car.crossing_ticks += 1;
+            }
+            car.pass_junction(&all_cars, &self.model.config);
+        }
+        self.move_pedestrians();
+        //remove from list cars that are no longer on the screen
+        self.remove_old_cars();
+        self.detect_collisions();
+        for junction in &mut self.model.junctions {
+            junction.traffic_light_switch.tick();
+        }
+        (0..self.model.junctions.len())
+            .map(|junction| Observation::new(&self.model, junction, self.tick))
+            .collect()
+    }
+
+    //change traffic lights of every crossing, emergency vehicles calling green overrule the controllers
+    fn switch_lights(&mut self, decisions: &[Decision], observations: &[Observation]) {
+        for ((junction, decision), observation) in self
+            .model
+            .junctions
+            .iter_mut()
+            .zip(decisions)
+            .zip(observations)
+        {
+            let switch = &mut junction.traffic_light_switch;
+            let decision = switch
+                .preempt(&observation.emergencies)
+                .unwrap_or_else(|| decision.clone());
+            //a call starts with nothing elapsed
+            if switch
+                .preemption
+                .as_ref()
+                .is_some_and(|preemption| preemption.elapsed == 0)
+            {
+                self.kpis.record_call();
+            }
+            switch.apply(&decision, observation.crossing_clear);
+            if let Some(response) = switch.response_time() {
+                self.kpis.record_preemption(response);
+            }
+        }
+        self.kpis.record_tick(observations);
+        self.tick += 1;
+    }
+
+    fn spawn_scenario_cars(&mut self) {
+        let time = self.time();
+        if let Some(runner) = &mut self.scenario {
+            for (entry, route, kind) in runner.due(time, &mut self.model.rng) {
+                if !self.model.spawn_on_route(&entry, route.clone(), kind) {
+                    runner.postpone(entry, route, kind);
+                }
+            }
+        }
+    }
+
+    //people step onto or along their zebra once the cars have moved, and leave it on the far kerb
+    fn move_pedestrians(&mut self) {
+        let time = self.time();
+        if let Some(runner) = &mut self.scenario {
+            for (junction, arm) in runner.due_pedestrians(time, &mut self.model.rng) {
+                let far = self.model.rng.random_bool(0.5);
+                self.model.spawn_pedestrian(junction, arm, far);
+            }
+        }
+        let model = &mut self.model;
+        for pedestrian in &mut model.pedestrians {
+            let switch = &model.junctions[pedestrian.junction].traffic_light_switch;
+            pedestrian.step(switch.walk[&pedestrian.arm], &model.cars, &model.config);
+        }
+        let kpis = &mut self.kpis;
+        model.pedestrians.retain(|pedestrian| {
+            if pedestrian.has_crossed() {
+                kpis.record_crossing(pedestrian);
+            }
+            !pedestrian.has_crossed()
+        });
+    }
+
+    //log every pair of cars that starts to overlap
+    fn detect_collisions(&mut self) {
+        let boxes: Vec<Obb> = self.model.cars.iter().map(Obb::of).collect();
+        let mut colliding = HashSet::new();
+        for (i, first) in self.model.cars.iter().enumerate() {
+            for (j, second) in self.model.cars.iter().enumerate().skip(i + 1) {
+                if !boxes[i].overlaps(&boxes[j]) {
+                    continue;
+                }
+                let pair = (first.id.min(second.id), first.id.max(second.id));
+                if !self.colliding.contains(&pair) {
+                    let collision = Collision::new(self.tick, first, second);
+                    eprintln!("{}", collision.describe());
+                    self.collisions.push(collision);
+                    if self.pause_on_collision {
+                        self.paused = true;
+                    }
+                }
+                colliding.insert(pair);
+            }
+        }
+        self.colliding = colliding;
+    }
+
+    //ids of cars overlapping another car right now
+    pub fn crashed_cars(&self) -> Vec<u64> {
+        self.colliding
+            .iter()
+            .flat_map(|(first, second)| [*first, *second])
+            .collect()
+    }
+
+    fn remove_old_cars(&mut self) {
+        if let Some(index) = self.model.cars.iter().position(Car::is_off_screen) {
+            let car = self.model.cars.remove(index);
+            let trip = Trip::new(&car, self.tick, &self.model.config.following);
+            self.kpis.record_trip(&trip);
+            self.trips.push(trip);
+            self.throughput += 1;
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::constants::*;
+use crate::model::Destination;
+use serde::Deserialize;
+
+//what drives along the lanes, everything shares the lanes and the lights
+#[derive(Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum VehicleKind {
+    #[default]
+    Car,
+    //slower and narrower, waits at the advanced stop line of a bike box
+    Bicycle,
+    Motorbike,
+    //long ones wait further back at the stop line to swing round the corner on a wider arc
+    Truck,
+    Bus,
+    //calls green for its approach at signals, the others hold for it at roundabouts
+    Emergency,
+}
+
+impl VehicleKind {
+    pub fn all() -> Vec<VehicleKind> {
+        vec![
+            VehicleKind::Car,
+            VehicleKind::Bicycle,
+            VehicleKind::Motorbike,
+            VehicleKind::Truck,
+            VehicleKind::Bus,
+            VehicleKind::Emergency,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VehicleKind::Car => "car",
+            VehicleKind::Bicycle => "bicycle",
+            VehicleKind::Motorbike => "motorbike",
+            VehicleKind::Truck => "truck",
+            VehicleKind::Bus => "bus",
+            VehicleKind::Emergency => "emergency",
+        }
+    }
+
+    //pixels from front to back
+    pub fn length(&self) -> i32 {
+        match self {
+            VehicleKind::Car => CAR_SIZE,
+            VehicleKind::Bicycle => BICYCLE_LENGTH,
+            VehicleKind::Motorbike => MOTORBIKE_LENGTH,
+            VehicleKind::Truck => TRUCK_LENGTH,
+            VehicleKind::Bus => BUS_LENGTH,
+            VehicleKind::Emergency => EMERGENCY_LENGTH,
+        }
+    }
+
+    //pixels from side to side
+    pub fn width(&self) -> i32 {
+        match self {
+            VehicleKind::Car => CAR_SIZE,
+            VehicleKind::Bicycle => BICYCLE_WIDTH,
+            VehicleKind::Motorbike => MOTORBIKE_WIDTH,
+            VehicleKind::Truck => TRUCK_WIDTH,
+            VehicleKind::Bus => BUS_WIDTH,
+            VehicleKind::Emergency => EMERGENCY_WIDTH,
+        }
+    }
+
+    //pixels per tick
+    pub fn max_speed(&self) -> f64 {
+        match self {
+            VehicleKind::Car => CAR_SPEED_F64,
+            VehicleKind::Bicycle => BICYCLE_SPEED,
+            VehicleKind::Motorbike => MOTORBIKE_SPEED,
+            VehicleKind::Truck => TRUCK_SPEED,
+            VehicleKind::Bus => BUS_SPEED,
+            VehicleKind::Emergency => EMERGENCY_SPEED,
+        }
+    }
+
+    //pixels per second squared
+    pub fn acceleration(&self) -> f64 {
+        match self {
+            VehicleKind::Car => CAR_ACCELERATION,
+            VehicleKind::Bicycle => BICYCLE_ACCELERATION,
+            VehicleKind::Motorbike => MOTORBIKE_ACCELERATION,
+            VehicleKind::Truck => TRUCK_ACCELERATION,
+            VehicleKind::Bus => BUS_ACCELERATION,
+            VehicleKind::Emergency => EMERGENCY_ACCELERATION,
+        }
+    }
+
+    //pixels, radius of the tightest arc the vehicle can drive, none for the nimble ones
+    pub fn turn_radius(&self) -> f64 {
+        match self {
+            VehicleKind::Truck => TRUCK_TURN_RADIUS,
+            VehicleKind::Bus => BUS_TURN_RADIUS,
+            _ => 0.0,
+        }
+    }
+
+    //sprite of a vehicle taking turn next
+    pub fn sprite(&self, turn: &Destination) -> &'static str {
+        let [left, ahead, right] = match self {
+            VehicleKind::Car => [BLUE_CAR_URL, WHITE_CAR_URL, ORANGE_CAR_URL],
+            VehicleKind::Bicycle => [BLUE_BICYCLE_URL, WHITE_BICYCLE_URL, ORANGE_BICYCLE_URL],
+            VehicleKind::Motorbike => [
+                BLUE_MOTORBIKE_URL,
+                WHITE_MOTORBIKE_URL,
+                ORANGE_MOTORBIKE_URL,
+            ],
+            VehicleKind::Truck => [BLUE_TRUCK_URL, WHITE_TRUCK_URL, ORANGE_TRUCK_URL],
+            VehicleKind::Bus => [BLUE_BUS_URL, WHITE_BUS_URL, ORANGE_BUS_URL],
+            VehicleKind::Emergency => [
+                BLUE_AMBULANCE_URL,
+                WHITE_AMBULANCE_URL,
+                ORANGE_AMBULANCE_URL,
+            ],
+        };
+        match turn {
+            Destination::Left | Destination::BearLeft => left,
+            Destination::Ahead => ahead,
+            Destination::Right | Destination::BearRight => right,
+        }
+    }
+}
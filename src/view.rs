@@ -1,13 +1,21 @@
-use super::Model;
+use crate::collision::Obb;
 use crate::constants::*;
+use crate::font::{glyph, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::lane::Lane;
 use crate::model::Car;
 use crate::model::Line;
 use crate::model::Location;
+use crate::model::Model;
+use crate::model::PointF;
+use crate::model::SignalState;
 use crate::model::TrafficLight;
+use crate::pedestrian::{Pedestrian, WalkSignal};
 use sdl2::image::LoadTexture;
 use sdl2::pixels::Color;
-use sdl2::rect::{Point, Rect};
+use sdl2::rect::{FPoint, Point, Rect};
+use sdl2::render::BlendMode;
 use sdl2::render::Canvas;
+use sdl2::render::Vertex;
 use sdl2::video::Window;
 
 pub struct View {
@@ -20,75 +28,94 @@ impl View {
         Self { canvas, bg_color }
     }
 
-    pub fn draw_model(&mut self, model: &mut Model) {
+    pub fn set_title(&mut self, title: &str) {
+        if let Err(e) = self.canvas.window_mut().set_title(title) {
+            println!("Cannot set window title: {:?}", e);
+        }
+    }
+
+    pub fn draw_model(&mut self, model: &Model, hud: Option<&[String]>, crashed: &[u64]) {
         let (r, g, b) = self.bg_color;
         self.canvas.set_draw_color(Color::RGB(r, g, b));
         self.canvas.clear();
         for marking in &model.road_marking {
-            self.draw_line(&marking);
+            self.draw_line(marking);
         }
 
         //init textures
-        let field_width = model.config.top_left.x as u32;
-        let field_heigth = model.config.top_left.y as u32;
         let texture_creator = self.canvas.texture_creator();
-        let center = Point::new((field_width / 2) as i32, (field_heigth / 2) as i32);
         match texture_creator.load_texture(LANDSCAPE_URL) {
             Ok(texture) => {
                 let query = texture.query();
                 let src = Rect::new(0, 0, query.width, query.height);
 
-                //draw background top-left
-                let dst = Rect::new(0, 0, field_width, field_heigth);
-                if let Err(e) = self
-                    .canvas
-                    .copy_ex(&texture, src, dst, 0.0, center, false, false)
-                {
-                    println!("Cannot copy texture: {:?}", e)
-                }
-
-                //draw background top-right
-                let x = field_width as i32 + CAR_SIZE * 2 + MARGIN * 2;
-                let dst = Rect::new(x, 0, field_width, field_heigth);
-                if let Err(e) = self
-                    .canvas
-                    .copy_ex(&texture, src, dst, 0.0, center, true, false)
-                {
-                    println!("Cannot copy texture: {:?}", e)
-                }
-
-                //draw background bottom-left
-                let y = field_heigth as i32 + CAR_SIZE * 2 + MARGIN * 2;
-                let dst = Rect::new(0, y, field_width, field_heigth);
-                if let Err(e) = self
-                    .canvas
-                    .copy_ex(&texture, src, dst, 0.0, center, false, true)
-                {
-                    println!("Cannot copy texture: {:?}", e)
+                //draw background between the roads, mirrored on the right and bottom half
+                //so the roadside of the picture faces the crossings
+                for dst in Self::fields(model) {
+                    let center = Point::new(dst.width() as i32 / 2, dst.height() as i32 / 2);
+                    let flip_horizontal = dst.center().x() > SCREEN_WIDTH / 2;
+                    let flip_vertical = dst.center().y() > SCREEN_HEIGHT / 2;
+                    if let Err(e) = self.canvas.copy_ex(
+                        &texture,
+                        src,
+                        dst,
+                        0.0,
+                        center,
+                        flip_horizontal,
+                        flip_vertical,
+                    ) {
+                        println!("Cannot copy texture: {:?}", e)
+                    }
                 }
+            }
+            Err(e) => println!("Cannot load texture: {:?}", e),
+        }
+        //skewed arms do not leave rectangles between the roads, the roads go over the landscape
+        if model.config.network.is_skewed() {
+            self.draw_roads(model);
+            for marking in &model.road_marking {
+                self.draw_line(marking);
+            }
+        }
 
-                //draw background bottom-right
-                let dst = Rect::new(x, y, field_width, field_heigth);
-                if let Err(e) = self
-                    .canvas
-                    .copy_ex(&texture, src, dst, 0.0, center, true, true)
-                {
-                    println!("Cannot copy texture: {:?}", e)
+        //draw traffic lights, a roundabout has none
+        if !model.config.layout.is_roundabout() {
+            for junction in &model.junctions {
+                for lights in junction.traffic_light_switch.traffic_lights.values() {
+                    lights.draw(&mut self.canvas);
                 }
             }
-            Err(e) => println!("Cannot load texture: {:?}", e),
         }
 
-        //draw traffic lights
-        model.traffic_light_switch.update(model.cars.clone());
-        for lights in model.traffic_light_switch.traffic_lights.values() {
-            lights.draw(&mut self.canvas);
+        if model.config.layout.zebras {
+            self.draw_pedestrians(model);
         }
 
         for car in &model.cars {
             car.draw(&mut self.canvas);
         }
 
+        //mark cars involved in a collision
+        let (r, g, b) = CRASH_COLOR;
+        self.canvas.set_draw_color(Color::RGB(r, g, b));
+        //the outline follows the car's box, so trucks and turning cars are framed too
+        for car in model.cars.iter().filter(|car| crashed.contains(&car.id)) {
+            for grow in 0..CRASH_OUTLINE {
+                let mut obb = Obb::of(car);
+                obb.half_length += (grow + 2) as f64;
+                obb.half_width += (grow + 2) as f64;
+                let mut corners: Vec<Point> = obb
+                    .corners()
+                    .iter()
+                    .map(|corner| Point::new(corner.x.round() as i32, corner.y.round() as i32))
+                    .collect();
+                corners.push(corners[0]);
+                if let Err(e) = self.canvas.draw_lines(corners.as_slice()) {
+                    println!("Could not draw on canvas: {:?}", e);
+                }
+            }
+        }
+
         //////////////////// points ////////////////////
         /*
         self.canvas.set_draw_color(Color::RGB(255, 255, 0));
@@ -122,9 +149,225 @@ impl View {
         */
         ///////////////////////////////////////////////
 
+        if let Some(lines) = hud {
+            self.draw_hud(lines);
+        }
+
         self.canvas.present();
     }
 
+    //walk signals on both kerbs of every zebra, dark ones stay off, and the people on or at it
+    fn draw_pedestrians(&mut self, model: &Model) {
+        let config = &model.config;
+        for (index, junction) in model.junctions.iter().enumerate() {
+            let crossing = &config.crossings[index];
+            for (arm, signal) in &junction.traffic_light_switch.walk {
+                let (r, g, b) = match signal {
+                    WalkSignal::Walk => WALK_COLOR,
+                    WalkSignal::Clearance => SIGNAL_COLOR_AMBER,
+                    WalkSignal::DontWalk => SIGNAL_COLOR_RED,
+                    WalkSignal::Dark => continue,
+                };
+                self.canvas.set_draw_color(Color::RGB(r, g, b));
+                let middle = crossing.across(arm, &crossing.center);
+                let kerb = Pedestrian::kerb(config) + PEDESTRIAN_SIZE_F64;
+                for cross in [middle - kerb, middle + kerb] {
+                    let point = Pedestrian::zebra_point(index, arm, cross, config);
+                    self.fill_square(&point, WALK_SIGNAL_SIZE);
+                }
+            }
+        }
+        let (r, g, b) = PEDESTRIAN_COLOR;
+        self.canvas.set_draw_color(Color::RGB(r, g, b));
+        for pedestrian in &model.pedestrians {
+            self.fill_square(&pedestrian.position(config), PEDESTRIAN_SIZE);
+        }
+    }
+
+    fn fill_square(&mut self, center: &PointF, size: i32) {
+        let rect = Rect::new(
+            center.x as i32 - size / 2,
+            center.y as i32 - size / 2,
+            size as u32,
+            size as u32,
+        );
+        if let Err(e) = self.canvas.fill_rect(rect) {
+            println!("Could not draw on canvas: {:?}", e);
+        }
+    }
+
+    //box and arms of every crossing in the colour of the road, out past the edge of the screen
+    fn draw_roads(&mut self, model: &Model) {
+        let (r, g, b) = self.bg_color;
+        let vertex = |x: f64, y: f64| Vertex {
+            position: FPoint::new(x as f32, y as f32),
+            color: Color::RGB(r, g, b),
+            tex_coord: FPoint::new(0.0, 0.0),
+        };
+        let reach = SCREEN_WIDTH_F64 + SCREEN_HEIGHT_F64;
+        let mut vertices = vec![];
+        let mut triangles: Vec<[u32; 3]> = vec![];
+        for crossing in &model.config.crossings {
+            let center = vertices.len() as u32;
+            vertices.push(vertex(crossing.center.x, crossing.center.y));
+            let corners = crossing.outline.len() as u32;
+            for corner in &crossing.outline {
+                vertices.push(vertex(corner.x, corner.y));
+            }
+            for at in 0..corners {
+                triangles.push([center, center + 1 + at, center + 1 + (at + 1) % corners]);
+            }
+            for (at, (_, angle)) in crossing.arms.iter().enumerate() {
+                let (dx, dy) = Lane::unit(*angle);
+                let first = vertices.len() as u32;
+                for corner in &crossing.outline[at * 2..at * 2 + 2] {
+                    vertices.push(vertex(corner.x, corner.y));
+                    vertices.push(vertex(corner.x + dx * reach, corner.y + dy * reach));
+                }
+                triangles.push([first, first + 1, first + 3]);
+                triangles.push([first, first + 3, first + 2]);
+            }
+        }
+        if let Err(e) = self
+            .canvas
+            .render_geometry(&vertices, None, triangles.as_slice())
+        {
+            println!("Could not draw on canvas: {:?}", e);
+        }
+    }
+
+    //land between the roads, the whole screen under skewed arms
+    fn fields(model: &Model) -> Vec<Rect> {
+        if model.config.network.is_skewed() {
+            return vec![Rect::new(0, 0, SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32)];
+        }
+        let half_width = model.config.layout.half_width();
+        let network = &model.config.network;
+        //edges of the roads from left to right and top to bottom
+        let mut columns = vec![0.0];
+        for column in 0..network.columns {
+            let x = network.center(column).x;
+            columns.extend([x - half_width, x + half_width]);
+        }
+        columns.push(SCREEN_WIDTH_F64);
+        let mut rows = vec![0.0];
+        for row in 0..network.rows {
+            let y = network.center(row * network.columns).y;
+            rows.extend([y - half_width, y + half_width]);
+        }
+        rows.push(SCREEN_HEIGHT_F64);
+        let mut fields = vec![];
+        for x in columns.chunks(2) {
+            for y in rows.chunks(2) {
+                fields.push(Rect::new(
+                    x[0] as i32,
+                    y[0] as i32,
+                    (x[1] - x[0]) as u32,
+                    (y[1] - y[0]) as u32,
+                ));
+            }
+        }
+        //sides without an arm are land up to half way to the next crossing or the screen edge
+        for index in 0..network.junctions() {
+            let center = network.center(index);
+            let half_way = |side: &Location| {
+                let next = network
+                    .neighbour(index, side)
+                    .map(|next| network.center(next));
+                match (side, next) {
+                    (Location::West | Location::East, Some(next)) => (center.x + next.x) / 2.0,
+                    (Location::North | Location::South, Some(next)) => (center.y + next.y) / 2.0,
+                    (Location::West | Location::North, None) => 0.0,
+                    (Location::East, None) => SCREEN_WIDTH_F64,
+                    (Location::South, None) => SCREEN_HEIGHT_F64,
+                    //arms off the corners only come with skewed networks
+                    _ => 0.0,
+                }
+            };
+            for side in Location::all() {
+                if network.has_arm(index, &side) {
+                    continue;
+                }
+                let (left, right, top, bottom) = match side {
+                    Location::West => (
+                        half_way(&side),
+                        center.x - half_width,
+                        center.y - half_width,
+                        center.y + half_width,
+                    ),
+                    Location::East => (
+                        center.x + half_width,
+                        half_way(&side),
+                        center.y - half_width,
+                        center.y + half_width,
+                    ),
+                    Location::North => (
+                        center.x - half_width,
+                        center.x + half_width,
+                        half_way(&side),
+                        center.y - half_width,
+                    ),
+                    Location::South => (
+                        center.x - half_width,
+                        center.x + half_width,
+                        center.y + half_width,
+                        half_way(&side),
+                    ),
+                    _ => continue,
+                };
+                fields.push(Rect::new(
+                    left as i32,
+                    top as i32,
+                    (right - left) as u32,
+                    (bottom - top) as u32,
+                ));
+            }
+        }
+        fields
+    }
+
+    fn draw_hud(&mut self, lines: &[String]) {
+        let char_width = (GLYPH_WIDTH + 1) * HUD_SCALE;
+        let line_height = (GLYPH_HEIGHT + 2) * HUD_SCALE;
+        let columns = lines.iter().map(|line| line.len()).max().unwrap_or(0) as i32;
+
+        //dim the scene behind the text
+        let (r, g, b, a) = HUD_BACKGROUND;
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(Color::RGBA(r, g, b, a));
+        let width = (columns * char_width + HUD_SCALE * 2) as u32;
+        let height = (lines.len() as i32 * line_height + HUD_SCALE * 2) as u32;
+        if let Err(e) = self.canvas.fill_rect(Rect::new(0, 0, width, height)) {
+            println!("Could not draw on canvas: {:?}", e);
+        }
+        self.canvas.set_blend_mode(BlendMode::None);
+
+        let (r, g, b) = HUD_TEXT_COLOR;
+        self.canvas.set_draw_color(Color::RGB(r, g, b));
+        let mut pixels = vec![];
+        for (row, line) in lines.iter().enumerate() {
+            let top = HUD_SCALE * 2 + row as i32 * line_height;
+            for (column, c) in line.chars().enumerate() {
+                let left = HUD_SCALE * 2 + column as i32 * char_width;
+                for (y, bits) in glyph(c).iter().enumerate() {
+                    for x in 0..GLYPH_WIDTH {
+                        if bits & (1 << (GLYPH_WIDTH - 1 - x)) != 0 {
+                            pixels.push(Rect::new(
+                                left + x * HUD_SCALE,
+                                top + y as i32 * HUD_SCALE,
+                                HUD_SCALE as u32,
+                                HUD_SCALE as u32,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        if let Err(e) = self.canvas.fill_rects(&pixels) {
+            println!("Could not draw on canvas: {:?}", e);
+        }
+    }
+
     fn draw_line(&mut self, line: &Line) {
         let (r, g, b) = line.color;
         self.canvas.set_draw_color(Color::RGB(r, g, b));
@@ -144,37 +387,19 @@ impl Drawable for Car {
             Ok(texture) => {
                 let query = texture.query();
                 let src = Rect::new(0, 0, query.width, query.height);
-                let dst = Rect::new(
-                    x - CAR_SIZE / 2,
-                    y - CAR_SIZE / 2,
-                    query.width,
-                    query.height,
-                );
+                //sprites face east and turn round their middle
+                let (width, height) = (query.width as i32, query.height as i32);
+                let dst = Rect::new(x - width / 2, y - height / 2, query.width, query.height);
                 //let dst = Rect::new(x, y , query.width, query.height);
-                let center = Point::new(CAR_SIZE / 2, CAR_SIZE / 2);
+                let center = Point::new(width / 2, height / 2);
                 if let Err(e) = canvas.copy_ex(&texture, src, dst, self.deg, center, false, false) {
                     println!("Cannot copy texture: {:?}", e);
-                    let (r, g, b) = self.color.color;
-                    canvas.set_draw_color(Color::RGB(r, g, b));
-                    let width = self.size.width as u32;
-                    let length = self.size.length as u32;
-                    let rect = Rect::new(x - CAR_SIZE / 2, y - CAR_SIZE / 2, width, length);
-                    if let Err(e) = canvas.fill_rect(rect) {
-                        println!("Could not draw on canvas: {:?}", e);
-                    }
+                    self.fill(canvas, x, y);
                 }
             }
             Err(e) => {
                 println!("Could not loat texture: {:?}", e);
-                let (r, g, b) = self.color.color;
-                canvas.set_draw_color(Color::RGB(r, g, b));
-                let width = self.size.width as u32;
-                let length = self.size.length as u32;
-                //let rect = Rect::new(x, y, width, length);
-                let rect = Rect::new(x - CAR_SIZE / 2, y - CAR_SIZE / 2, width, length);
-                if let Err(e) = canvas.fill_rect(rect) {
-                    println!("Could not draw on canvas: {:?}", e);
-                }
+                self.fill(canvas, x, y);
             }
         };
 
@@ -185,142 +410,98 @@ impl Drawable for Car {
     }
 }
 
+impl Car {
+    //plain rectangle in the colour of the car lying along the direction it drives
+    fn fill(&self, canvas: &mut Canvas<Window>, x: i32, y: i32) {
+        let (r, g, b) = self.color.color;
+        canvas.set_draw_color(Color::RGB(r, g, b));
+        let (sin, cos) = self.rad.sin_cos();
+        let (width, height) = match cos.abs() >= sin.abs() {
+            true => (self.size.length, self.size.width),
+            false => (self.size.width, self.size.length),
+        };
+        let rect = Rect::new(x - width / 2, y - height / 2, width as u32, height as u32);
+        if let Err(e) = canvas.fill_rect(rect) {
+            println!("Could not draw on canvas: {:?}", e);
+        }
+    }
+}
+
 impl Drawable for TrafficLight {
     fn draw(&self, canvas: &mut Canvas<Window>) {
-        let url = match self.status {
-            true => GO_SIGN_URL,
-            false => STOP_SIGN_URL,
+        //flashing lights are dark every other half second
+        if self.state == SignalState::FlashingAmber && (self.elapsed_time() * 2.0) as u64 % 2 == 1 {
+            return;
+        }
+        let url = match self.state {
+            SignalState::Green => GO_SIGN_URL,
+            SignalState::Red => STOP_SIGN_URL,
+            SignalState::RedAmber => RED_AMBER_SIGN_URL,
+            SignalState::Amber | SignalState::FlashingAmber => AMBER_SIGN_URL,
         };
 
-        let angle = match self.location {
-            Location::East => 90.0,
-            Location::West => 270.0,
-            Location::North => 0.0,
-            Location::South => 180.0,
-        };
+        //sprites stand upright on the arm to the north
+        let angle = (self.angle + 90.0).rem_euclid(360.0);
 
         let texture_creator = canvas.texture_creator();
         match texture_creator.load_texture(url) {
             Ok(texture) => {
                 let query = texture.query();
                 let src = Rect::new(0, 0, query.width, query.height);
-
-                let sign_position = match self.location {
-                    Location::East => Point::new(
-                        SCREEN_WIDTH / 2 + CAR_SIZE + MARGIN * 2,
-                        SCREEN_HEIGHT / 2 + CAR_SIZE + MARGIN * 2,
-                    ),
-                    Location::West => Point::new(
-                        SCREEN_WIDTH / 2 - CAR_SIZE - MARGIN * 2 - query.width as i32,
-                        SCREEN_HEIGHT / 2 - CAR_SIZE - MARGIN * 2 - query.height as i32,
-                    ),
-                    Location::North => Point::new(
-                        SCREEN_WIDTH / 2 + CAR_SIZE + MARGIN * 2,
-                        SCREEN_HEIGHT / 2 - CAR_SIZE - MARGIN * 2 - query.height as i32,
-                    ),
-                    Location::South => Point::new(
-                        SCREEN_WIDTH / 2 - CAR_SIZE - MARGIN * 2 - query.width as i32,
-                        SCREEN_HEIGHT / 2 + CAR_SIZE + MARGIN * 2,
-                    ),
-                };
-
+                let (width, height) = (query.width as i32, query.height as i32);
+                let sign_position = self.corner(MARGIN, width, height);
                 let dst = Rect::new(sign_position.x, sign_position.y, query.width, query.height);
                 let center = Point::new((query.width / 2) as i32, (query.height / 2) as i32);
 
                 if let Err(e) = canvas.copy_ex(&texture, src, dst, angle, center, true, true) {
                     println!("Cannot copy texture: {:?}", e);
-                    let (r, g, b) = match self.status {
-                        true => (0, 255, 0),
-                        false => (255, 0, 0),
-                    };
-
-                    let traffic_light_position = match self.location {
-                        Location::East => Point::new(
-                            (SCREEN_WIDTH + MARGIN) / 2 + CAR_SIZE + MARGIN,
-                            (SCREEN_HEIGHT + MARGIN) / 2 + CAR_SIZE + MARGIN,
-                        ),
-                        Location::West => Point::new(
-                            (SCREEN_WIDTH - CAR_SIZE * 2 - MARGIN) / 2
-                                - MARGIN
-                                - TRAFFIC_LIGHTS_WIDTH,
-                            (SCREEN_HEIGHT - CAR_SIZE * 2 - MARGIN) / 2
-                                - MARGIN
-                                - TRAFFIC_LIGHTS_HEIGTH,
-                        ),
-                        Location::North => Point::new(
-                            (SCREEN_WIDTH + MARGIN) / 2 + CAR_SIZE + MARGIN,
-                            (SCREEN_HEIGHT - CAR_SIZE * 2 - MARGIN) / 2
-                                - MARGIN
-                                - TRAFFIC_LIGHTS_HEIGTH,
-                        ),
-                        Location::South => Point::new(
-                            (SCREEN_WIDTH - CAR_SIZE * 2 - MARGIN) / 2
-                                - MARGIN
-                                - TRAFFIC_LIGHTS_WIDTH,
-                            (SCREEN_HEIGHT + MARGIN) / 2 + CAR_SIZE + MARGIN,
-                        ),
-                    };
-
-                    canvas.set_draw_color(Color::RGB(r, g, b));
-                    let width = self.size.width as u32;
-                    let length = self.size.length as u32;
-                    let rect = Rect::new(
-                        traffic_light_position.x,
-                        traffic_light_position.y,
-                        width,
-                        length,
-                    );
-                    if let Err(e) = canvas.fill_rect(rect) {
-                        println!("Could not draw on canvas: {:?}", e);
-                    }
+                    self.fill(canvas);
                 }
             }
             Err(e) => {
                 println!("Could not load texture: {:?}", e);
-                let (r, g, b) = match self.status {
-                    true => (0, 255, 0),
-                    false => (255, 0, 0),
-                };
-
-                let traffic_light_position = match self.location {
-                    Location::East => Point::new(
-                        (SCREEN_WIDTH + MARGIN) / 2 + CAR_SIZE + MARGIN,
-                        (SCREEN_HEIGHT + MARGIN) / 2 + CAR_SIZE + MARGIN,
-                    ),
-                    Location::West => Point::new(
-                        (SCREEN_WIDTH - CAR_SIZE * 2 - MARGIN) / 2 - MARGIN - TRAFFIC_LIGHTS_WIDTH,
-                        (SCREEN_HEIGHT - CAR_SIZE * 2 - MARGIN) / 2
-                            - MARGIN
-                            - TRAFFIC_LIGHTS_HEIGTH,
-                    ),
-                    Location::North => Point::new(
-                        (SCREEN_WIDTH + MARGIN) / 2 + CAR_SIZE + MARGIN,
-                        (SCREEN_HEIGHT - CAR_SIZE * 2 - MARGIN) / 2
-                            - MARGIN
-                            - TRAFFIC_LIGHTS_HEIGTH,
-                    ),
-                    Location::South => Point::new(
-                        (SCREEN_WIDTH - CAR_SIZE * 2 - MARGIN) / 2 - MARGIN - TRAFFIC_LIGHTS_WIDTH,
-                        (SCREEN_HEIGHT + MARGIN) / 2 + CAR_SIZE + MARGIN,
-                    ),
-                };
-
-                canvas.set_draw_color(Color::RGB(r, g, b));
-                let width = self.size.width as u32;
-                let length = self.size.length as u32;
-                let rect = Rect::new(
-                    traffic_light_position.x,
-                    traffic_light_position.y,
-                    width,
-                    length,
-                );
-                if let Err(e) = canvas.fill_rect(rect) {
-                    println!("Could not draw on canvas: {:?}", e);
-                }
+                self.fill(canvas);
             }
         };
     }
 }
+
+impl TrafficLight {
+    //top left of a width by height box gap pixels off the box of the crossing,
+    //on the kerb clockwise of the arm
+    fn corner(&self, gap: i32, width: i32, height: i32) -> Point {
+        let (dx, dy) = Lane::unit(self.angle);
+        //out along the arm and across to its clockwise kerb
+        let (sx, sy) = (dx - dy, dy + dx);
+        let out = (self.edge + gap) as f64;
+        let x = self.center.x as f64 + sx * out + (sx - 1.0) * width as f64 / 2.0;
+        let y = self.center.y as f64 + sy * out + (sy - 1.0) * height as f64 / 2.0;
+        Point::new(x.round() as i32, y.round() as i32)
+    }
+
+    //plain rectangle in the colour of the light
+    fn fill(&self, canvas: &mut Canvas<Window>) {
+        let (r, g, b) = signal_color(self.state);
+        let position = self.corner(MARGIN / 2, TRAFFIC_LIGHTS_WIDTH, TRAFFIC_LIGHTS_HEIGTH);
+        canvas.set_draw_color(Color::RGB(r, g, b));
+        let width = self.size.width as u32;
+        let length = self.size.length as u32;
+        let rect = Rect::new(position.x, position.y, width, length);
+        if let Err(e) = canvas.fill_rect(rect) {
+            println!("Could not draw on canvas: {:?}", e);
+        }
+    }
+}
+
+fn signal_color(state: SignalState) -> (u8, u8, u8) {
+    match state {
+        SignalState::Green => SIGNAL_COLOR_GREEN,
+        SignalState::Red => SIGNAL_COLOR_RED,
+        SignalState::RedAmber => SIGNAL_COLOR_RED_AMBER,
+        SignalState::Amber | SignalState::FlashingAmber => SIGNAL_COLOR_AMBER,
+    }
+}
+
 trait Drawable {
     fn draw(&self, canvas: &mut Canvas<Window>);
 }
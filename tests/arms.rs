@@ -0,0 +1,290 @@
+use road_intersection::cli::Args;
+use road_intersection::layout::Layout;
+use road_intersection::model::{Destination, Location, Model, SignalState};
+use road_intersection::network::Network;
+use road_intersection::scenario::Scenario;
+use road_intersection::simulation::Simulation;
+use road_intersection::vehicle::VehicleKind;
+use std::collections::HashMap;
+
+//a crossroads with a fifth arm between the east and the south one
+fn five_arms() -> Network {
+    Network {
+        arms: vec![vec![
+            Location::West,
+            Location::East,
+            Location::North,
+            Location::South,
+            Location::SouthEast,
+        ]],
+        angles: HashMap::from([(Location::SouthEast, 50.0)]),
+        ..Network::default()
+    }
+}
+
+//pixels from the centre of crossing 0 to where a car from side waits to take turn
+fn setback(network: &Network, layout: &Layout, side: Location, turn: Destination) -> f64 {
+    let crossing = network.crossing(0, layout);
+    let lane = layout.approach(&crossing, &side, 0);
+    let exit = network.turned(0, &side.opposite(), &turn).unwrap();
+    lane.along(&crossing.center) - layout.stop_line(&crossing, &lane, VehicleKind::Car, &exit)
+}
+
+#[test]
+fn fifth_arm_bears_off_to_its_neighbours() {
+    let network = five_arms();
+    let from_southeast = Location::NorthWest;
+    assert_eq!(
+        network.turns(0, &from_southeast),
+        [
+            Destination::Left,
+            Destination::Right,
+            Destination::Ahead,
+            Destination::BearLeft
+        ]
+    );
+    let exits: Vec<_> = network
+        .turns(0, &from_southeast)
+        .iter()
+        .map(|turn| network.turned(0, &from_southeast, turn).unwrap())
+        .collect();
+    assert_eq!(
+        exits,
+        [
+            Location::South,
+            Location::East,
+            Location::North,
+            Location::West
+        ]
+    );
+}
+
+#[test]
+fn sharpest_turn_to_a_side_comes_before_bearing_that_way() {
+    let network = five_arms();
+    assert_eq!(
+        network.turned(0, &Location::East, &Destination::Right),
+        Some(Location::South)
+    );
+    assert_eq!(
+        network.turned(0, &Location::East, &Destination::BearRight),
+        Some(Location::SouthEast)
+    );
+    assert_eq!(
+        network.turned(0, &Location::South, &Destination::Ahead),
+        Some(Location::South)
+    );
+    assert_eq!(
+        network.turned(0, &Location::North, &Destination::Right),
+        Some(Location::SouthEast)
+    );
+    assert_eq!(
+        network.turned(0, &Location::North, &Destination::BearRight),
+        Some(Location::East)
+    );
+    assert_eq!(
+        network.turned(0, &Location::East, &Destination::BearLeft),
+        None
+    );
+}
+
+#[test]
+fn stop_lines_move_back_where_arms_close_in() {
+    let network = five_arms();
+    let layout = Layout::default();
+    let crossing = network.crossing(0, &layout);
+    let square = layout.half_width();
+    assert_eq!(crossing.edge(&Location::West), square);
+    assert_eq!(crossing.edge(&Location::North), square);
+    for side in [Location::East, Location::South, Location::SouthEast] {
+        assert!(crossing.edge(&side) > square, "{}", side.name());
+    }
+    let west = setback(&network, &layout, Location::West, Destination::Ahead);
+    let east = setback(&network, &layout, Location::East, Destination::Ahead);
+    assert_eq!(
+        west,
+        setback(
+            &Network::default(),
+            &layout,
+            Location::West,
+            Destination::Ahead
+        )
+    );
+    assert!(east > west);
+}
+
+#[test]
+fn far_side_turns_from_a_deep_arm_start_their_arc_in_the_box() {
+    let layout = Layout::default();
+    for (network, expected) in [(Network::default(), false), (five_arms(), true)] {
+        let crossing = network.crossing(0, &layout);
+        let lane = layout.approach(&crossing, &Location::East, 0);
+        let exit = network
+            .turned(0, &Location::West, &Destination::Right)
+            .unwrap();
+        let stop_line = layout.stop_line(&crossing, &lane, VehicleKind::Car, &exit);
+        let start = layout.turn_start(&crossing, &lane, VehicleKind::Car, &exit);
+        assert_eq!(start > stop_line, expected);
+        assert!(start >= stop_line);
+    }
+}
+
+#[test]
+fn arms_too_close_together_are_rejected() {
+    let mut network = five_arms();
+    network.angles.insert(Location::SouthEast, 75.0);
+    assert!(network.validate(&Layout::default()).is_err());
+}
+
+#[test]
+fn turned_arms_need_a_single_crossing() {
+    let network = Network {
+        columns: 2,
+        angles: HashMap::from([(Location::North, 250.0)]),
+        ..Network::default()
+    };
+    assert!(network.is_skewed());
+    assert!(network.validate(&Layout::default()).is_err());
+}
+
+#[test]
+fn arms_lean_at_most_forty_five_degrees() {
+    //arms leaning far enough for bear turns need lanes for them
+    let layout = Layout {
+        lanes: vec![vec![
+            Destination::Left,
+            Destination::BearLeft,
+            Destination::Ahead,
+            Destination::BearRight,
+            Destination::Right,
+        ]],
+        ..Layout::default()
+    };
+    for (angle, leans) in [(250.0, true), (225.0, true), (224.0, false), (316.0, false)] {
+        let network = Network {
+            angles: HashMap::from([(Location::North, angle)]),
+            ..Network::default()
+        };
+        assert_eq!(network.validate(&layout).is_ok(), leans, "{}", angle);
+    }
+}
+
+#[test]
+fn arm_angles_stay_within_one_turn() {
+    for angle in [-10.0, 360.0, f64::NAN, f64::INFINITY] {
+        let network = Network {
+            angles: HashMap::from([(Location::East, angle)]),
+            ..Network::default()
+        };
+        assert!(network.validate(&Layout::default()).is_err(), "{}", angle);
+    }
+}
+
+#[test]
+fn arms_go_on_the_eight_sides_of_the_compass() {
+    assert_eq!(Location::compass().len(), 8);
+    let text = include_str!("../scenarios/five_arms.toml");
+    assert!(Scenario::parse(text).is_ok());
+    assert!(Scenario::parse(&text.replace("southeast", "southsoutheast")).is_err());
+}
+
+#[test]
+fn off_compass_arms_need_a_single_crossing() {
+    let network = Network {
+        rows: 2,
+        arms: vec![vec![
+            Location::West,
+            Location::East,
+            Location::North,
+            Location::SouthWest,
+        ]],
+        ..Network::default()
+    };
+    assert!(network.is_skewed());
+    assert!(network.validate(&Layout::default()).is_err());
+}
+
+#[test]
+fn bear_turns_need_a_lane_of_their_own() {
+    assert!(five_arms().validate(&Layout::default()).is_err());
+    let layout = Layout {
+        lanes: vec![vec![
+            Destination::Left,
+            Destination::BearLeft,
+            Destination::Ahead,
+            Destination::BearRight,
+            Destination::Right,
+        ]],
+        ..Layout::default()
+    };
+    assert!(five_arms().validate(&layout).is_ok());
+}
+
+//same wiring as the headless binary
+fn collisions(text: &str, seed: u64) -> usize {
+    let scenario = Scenario::parse(text).unwrap();
+    let signal = scenario.signal.clone();
+    let mut simulation = Simulation::with_scenario(Model::with_seed(seed), scenario);
+    simulation.traffic_controllers = simulation
+        .model
+        .junctions
+        .iter()
+        .map(|_| signal.build())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    simulation.model.set_timing(&signal.timing());
+    simulation.model.set_offsets(&signal.offsets());
+    while !simulation.is_finished() {
+        simulation.advance();
+    }
+    simulation.collisions.len()
+}
+
+#[test]
+fn five_arm_crossing_runs_without_collisions() {
+    let text = include_str!("../scenarios/five_arms.toml");
+    for seed in [6, 2024] {
+        assert_eq!(collisions(text, seed), 0, "seed {}", seed);
+    }
+}
+
+#[test]
+fn skewed_crossroads_runs_without_collisions() {
+    let text = include_str!("../scenarios/skewed.toml");
+    assert_eq!(collisions(text, 2024), 0);
+}
+
+#[test]
+fn fixed_controller_over_an_actuated_scenario_serves_every_arm() {
+    let text = include_str!("../scenarios/five_arms.toml");
+    let start = text.find("[signal]").unwrap();
+    let end = text.find("[[generator]]").unwrap();
+    let text = format!(
+        "{}[signal]\ncontroller = \"actuated\"\n\n{}",
+        &text[..start],
+        &text[end..]
+    );
+    let path = std::env::temp_dir().join("road_intersection_five_arms_actuated.toml");
+    std::fs::write(&path, text).unwrap();
+    let args = [
+        "--scenario",
+        &path.to_string_lossy(),
+        "--controller",
+        "fixed",
+    ]
+    .map(String::from);
+    let mut simulation = Args::parse_from(args.into_iter())
+        .unwrap()
+        .build_simulation()
+        .unwrap();
+    //the fifth arm gets its own phase after the two pairs of opposite arms
+    let mut served = false;
+    while simulation.time() < 120.0 && !served {
+        simulation.advance();
+        let lights = &simulation.model.junctions[0]
+            .traffic_light_switch
+            .traffic_lights;
+        served = lights[&Location::SouthEast].state == SignalState::Green;
+    }
+    assert!(served);
+}
@@ -0,0 +1,100 @@
+use road_intersection::constants::*;
+use road_intersection::model::{Location, SignalState, TrafficLightSwitch};
+use road_intersection::signal::{Decision, SignalTiming};
+use std::collections::HashMap;
+
+//seconds after west lost green to a call from the north at which a light changed, and to what
+fn changes(timing: &SignalTiming) -> Vec<(f64, Location, SignalState)> {
+    let mut switch = TrafficLightSwitch {
+        traffic_lights: TrafficLightSwitch::create_traffic_lights(&Location::all()),
+        walk: HashMap::new(),
+        pedestrian_phase: false,
+        timing: timing.clone(),
+        offset: 0.0,
+        preemption: None,
+    };
+    switch
+        .traffic_lights
+        .get_mut(&Location::West)
+        .unwrap()
+        .set_state(SignalState::Green);
+    let north = Decision::Force(vec![Location::North]);
+    let mut changes = vec![];
+    for tick in 1..=(10.0 * TICKS_PER_SECOND) as u64 {
+        let before: Vec<_> = [Location::West, Location::North]
+            .map(|location| switch.traffic_lights[&location].state)
+            .into();
+        switch.tick();
+        switch.apply(&north, true);
+        for (location, state) in [Location::West, Location::North].into_iter().zip(before) {
+            let now = switch.traffic_lights[&location].state;
+            if now != state {
+                changes.push((tick as f64 * TICK_DURATION, location, now));
+            }
+        }
+    }
+    changes
+}
+
+#[test]
+fn amber_then_all_red_then_red_amber_before_green() {
+    let timing = SignalTiming {
+        amber: 3.0,
+        red_amber: 1.0,
+        all_red: 2.0,
+        ..SignalTiming::default()
+    };
+    let changes = changes(&timing);
+    let expected = [
+        (0.0, Location::West, SignalState::Amber),
+        (3.0, Location::West, SignalState::Red),
+        (5.0, Location::North, SignalState::RedAmber),
+        (6.0, Location::North, SignalState::Green),
+    ];
+    assert_eq!(changes.len(), expected.len(), "{:?}", changes);
+    for ((time, location, state), (at, side, expected)) in changes.into_iter().zip(expected) {
+        assert_eq!((location, state), (side, expected));
+        //a change shows within a few ticks of its interval running out
+        assert!((time - at).abs() < 0.05, "{:?} at {} s", state, time);
+    }
+}
+
+#[test]
+fn negative_clearance_is_rejected() {
+    let timing = SignalTiming {
+        all_red: -1.0,
+        ..SignalTiming::default()
+    };
+    assert!(timing.validate().is_err());
+}
+
+#[test]
+fn endless_times_are_rejected() {
+    for time in [f64::NAN, f64::INFINITY] {
+        let timings = [
+            SignalTiming {
+                amber: time,
+                ..SignalTiming::default()
+            },
+            SignalTiming {
+                red_amber: time,
+                ..SignalTiming::default()
+            },
+            SignalTiming {
+                all_red: time,
+                ..SignalTiming::default()
+            },
+            SignalTiming {
+                min_green: time,
+                ..SignalTiming::default()
+            },
+            SignalTiming {
+                max_green: time,
+                ..SignalTiming::default()
+            },
+        ];
+        for timing in timings {
+            assert!(timing.validate().is_err(), "{:?}", timing);
+        }
+    }
+}
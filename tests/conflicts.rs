@@ -0,0 +1,124 @@
+use road_intersection::conflict::{ConflictTable, Movement};
+use road_intersection::constants::*;
+use road_intersection::layout::Layout;
+use road_intersection::model::{Destination, Location, Model};
+use road_intersection::network::Network;
+use road_intersection::vehicle::VehicleKind;
+use std::rc::Rc;
+
+//a car coming from from and taking turn
+fn car(from: Location, turn: Destination) -> Movement {
+    Movement::new(from, turn, VehicleKind::Car)
+}
+
+#[test]
+fn opposing_right_turns_conflict() {
+    let table = ConflictTable::shared(&Layout::default(), &Network::default());
+    let north = car(Location::North, Destination::Right);
+    let south = car(Location::South, Destination::Right);
+    assert!(table.conflicts(&north, &south));
+    assert!(table.conflicts(&south, &north));
+}
+
+#[test]
+fn opposing_straight_movements_share_green() {
+    let table = ConflictTable::shared(&Layout::default(), &Network::default());
+    for turn in [Destination::Ahead, Destination::Left] {
+        let north = car(Location::North, turn.clone());
+        let south = car(Location::South, turn);
+        assert!(!table.conflicts(&north, &south), "{:?}", north);
+    }
+    let west = car(Location::West, Destination::Ahead);
+    assert!(table.conflicts(&car(Location::North, Destination::Ahead), &west));
+}
+
+#[test]
+fn followers_from_the_same_approach_never_conflict() {
+    let table = ConflictTable::shared(&Layout::default(), &Network::default());
+    for first in Movement::all() {
+        for second in Movement::all().iter().filter(|m| m.from == first.from) {
+            assert!(!table.conflicts(&first, second), "{:?} {:?}", first, second);
+        }
+    }
+}
+
+#[test]
+fn conflicts_go_both_ways() {
+    let table = ConflictTable::shared(&Layout::default(), &Network::default());
+    assert!(!table.is_empty());
+    for first in Movement::all() {
+        for second in Movement::all() {
+            assert_eq!(
+                table.conflicts(&first, &second),
+                table.conflicts(&second, &first),
+                "{:?} {:?}",
+                first,
+                second
+            );
+        }
+    }
+}
+
+#[test]
+fn equal_layouts_share_one_table() {
+    let first = ConflictTable::shared(&Layout::default(), &Network::default());
+    let second = ConflictTable::shared(&Layout::default(), &Network::default());
+    assert!(Rc::ptr_eq(&first, &second));
+}
+
+#[test]
+fn tables_unused_for_longest_are_dropped() {
+    let network = Network::default();
+    let first = ConflictTable::shared(&Layout::default(), &network);
+    //other layouts fill the cache, asking for the first again keeps it
+    let layouts: Vec<Layout> = [(true, false), (false, true), (true, true)]
+        .into_iter()
+        .map(|(zebras, bike_boxes)| Layout {
+            zebras,
+            bike_boxes,
+            ..Layout::default()
+        })
+        .collect();
+    let oldest = ConflictTable::shared(&layouts[0], &network);
+    for layout in &layouts[1..] {
+        ConflictTable::shared(layout, &network);
+    }
+    assert!(Rc::ptr_eq(
+        &first,
+        &ConflictTable::shared(&Layout::default(), &network)
+    ));
+    //one layout more than fits pushes out the one asked for longest ago
+    let two_lanes = Layout {
+        lanes: vec![vec![Destination::Left, Destination::Ahead, Destination::Right]; 2],
+        ..Layout::default()
+    };
+    ConflictTable::shared(&two_lanes, &network);
+    assert!(Rc::ptr_eq(
+        &first,
+        &ConflictTable::shared(&Layout::default(), &network)
+    ));
+    assert!(!Rc::ptr_eq(
+        &oldest,
+        &ConflictTable::shared(&layouts[0], &network)
+    ));
+}
+
+#[test]
+fn time_to_conflict_follows_the_speed_of_the_vehicle() {
+    let mut model = Model::with_seed(1);
+    assert!(model.spawn_car(Location::West, 0, Destination::Ahead));
+    let config = &model.config;
+    let mut car = model.cars[0].clone();
+    //at full speed a car drives a pixel a tick
+    assert!((car.seconds_to(120.0, config) - 2.0).abs() < 1e-9);
+    //a slower truck at the same distance leaves more time
+    car.kind = VehicleKind::Truck;
+    car.speed = TRUCK_SPEED;
+    assert!(car.seconds_to(CRITICAL_GAP * TICKS_PER_SECOND, config) > CRITICAL_GAP);
+    //a standing car first has to speed up
+    car.kind = VehicleKind::Car;
+    car.speed = 0.0;
+    let distance = CAR_ACCELERATION / 2.0;
+    assert!((car.seconds_to(distance, config) - 1.0).abs() < 1e-9);
+    assert_eq!(car.seconds_to(-0.5, config), 0.0);
+}
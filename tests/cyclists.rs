@@ -0,0 +1,63 @@
+use road_intersection::constants::*;
+use road_intersection::model::{Car, Model};
+use road_intersection::scenario::Scenario;
+use road_intersection::simulation::Simulation;
+
+//a single vehicle from the west late in the green for the north and south
+fn waiting(vehicle: &str, bike_boxes: bool) -> Car {
+    let text = format!(
+        r#"
+duration = 25.0
+
+[layout]
+bike_boxes = {}
+
+[signal]
+controller = "fixed"
+
+[[signal.phase]]
+green = ["north", "south"]
+duration = 30.0
+
+[[signal.phase]]
+green = ["west", "east"]
+duration = 30.0
+
+[[spawn]]
+time = 0.0
+from = "west"
+turn = "ahead"
+vehicle = "{}"
+"#,
+        bike_boxes, vehicle
+    );
+    let scenario = Scenario::parse(&text).unwrap();
+    let signal = scenario.signal.clone();
+    let mut simulation = Simulation::with_scenario(Model::with_seed(1), scenario);
+    simulation.traffic_controllers = vec![signal.build().unwrap()];
+    simulation.model.set_timing(&signal.timing());
+    while !simulation.is_finished() {
+        simulation.advance();
+    }
+    simulation.model.cars[0].clone()
+}
+
+//pixels short of its stop line the vehicle stands
+fn short_of_the_line(car: &Car) -> f64 {
+    assert_eq!(car.speed, 0.0);
+    car.stop_line_distance().unwrap()
+}
+
+#[test]
+fn cyclists_wait_in_the_bike_box_and_cars_behind_it() {
+    let bicycle = short_of_the_line(&waiting("bicycle", true));
+    let car = short_of_the_line(&waiting("car", true));
+    assert!(bicycle.abs() < 1.0, "{}", bicycle);
+    assert!((car - BIKE_BOX_DEPTH).abs() < 1.0, "{}", car);
+}
+
+#[test]
+fn without_a_bike_box_cars_use_the_stop_line() {
+    let car = short_of_the_line(&waiting("car", false));
+    assert!(car.abs() < 1.0, "{}", car);
+}
@@ -0,0 +1,47 @@
+use road_intersection::model::Model;
+use road_intersection::scenario::Scenario;
+use road_intersection::simulation::Simulation;
+
+//random arrivals with random turns from two sides
+const ARRIVALS: &str = r#"
+duration = 60.0
+
+[[generator]]
+from = "west"
+arrivals = { kind = "poisson", rate = 0.4 }
+
+[[generator]]
+from = "north"
+arrivals = { kind = "poisson", rate = 0.3 }
+"#;
+
+//kpis of the run and where every car still on the road is when it ends
+fn run(seed: u64) -> (String, Vec<String>) {
+    let scenario = Scenario::parse(ARRIVALS).unwrap();
+    let mut simulation = Simulation::with_scenario(Model::with_seed(seed), scenario);
+    while !simulation.is_finished() {
+        simulation.advance();
+    }
+    let cars = simulation
+        .model
+        .cars
+        .iter()
+        .map(|car| {
+            format!(
+                "{:?} {:?} {:?}",
+                car.direction, car.destination, car.position
+            )
+        })
+        .collect();
+    (simulation.kpis.summary(), cars)
+}
+
+#[test]
+fn same_seed_replays_the_same_run() {
+    assert_eq!(run(7), run(7));
+}
+
+#[test]
+fn another_seed_gives_another_run() {
+    assert_ne!(run(7), run(8));
+}
@@ -0,0 +1,34 @@
+use road_intersection::env::{EnvConfig, Environment};
+use road_intersection::model::Location;
+use road_intersection::scenario::Scenario;
+
+//the crossing with a fifth arm from the southeast
+fn five_arms() -> Scenario {
+    Scenario::parse(include_str!("../scenarios/five_arms.toml")).unwrap()
+}
+
+#[test]
+fn actions_are_the_arms_of_the_crossing() {
+    let environment = Environment::new(five_arms(), EnvConfig::default()).unwrap();
+    let actions = environment.action_space();
+    assert_eq!(actions.len(), 5);
+    assert!(actions.contains(&Location::SouthEast));
+    let crossroads = Scenario::parse("duration = 60.0").unwrap();
+    let environment = Environment::new(crossroads, EnvConfig::default()).unwrap();
+    assert_eq!(environment.action_space(), Location::all());
+}
+
+#[test]
+fn endless_decision_intervals_are_rejected() {
+    for interval in [f64::NAN, f64::INFINITY, 0.0] {
+        let config = EnvConfig {
+            decision_interval: interval,
+            ..EnvConfig::default()
+        };
+        assert!(
+            Environment::new(five_arms(), config).is_err(),
+            "{}",
+            interval
+        );
+    }
+}
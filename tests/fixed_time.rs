@@ -0,0 +1,115 @@
+use road_intersection::constants::*;
+use road_intersection::model::Location;
+use road_intersection::signal::{
+    Decision, FixedTimeController, Interval, Observation, Phase, SignalPlan, TrafficController,
+};
+use std::collections::HashMap;
+
+//east-west for 20 s and north-south for 10 s, each starting with 1 s red amber
+//and followed by 3 s amber and 2 s all red
+fn plan(cycle: Option<f64>) -> SignalPlan {
+    SignalPlan {
+        phase: vec![
+            Phase {
+                green: vec![Location::West, Location::East],
+                duration: 20.0,
+            },
+            Phase {
+                green: vec![Location::North, Location::South],
+                duration: 10.0,
+            },
+        ],
+        amber: 3.0,
+        all_red: 2.0,
+        red_amber: 1.0,
+        cycle,
+        offsets: vec![],
+    }
+}
+
+#[test]
+fn phases_run_in_turn_through_the_cycle() {
+    let controller = FixedTimeController::new(plan(None)).unwrap();
+    assert_eq!(controller.plan.cycle_length(), 40.0);
+    for (time, state) in [
+        (0.0, (0, Interval::RedAmber)),
+        (1.0, (0, Interval::Green)),
+        (19.9, (0, Interval::Green)),
+        (20.0, (0, Interval::Amber)),
+        (23.0, (0, Interval::AllRed)),
+        (25.0, (1, Interval::RedAmber)),
+        (26.0, (1, Interval::Green)),
+        (35.0, (1, Interval::Amber)),
+        (38.0, (1, Interval::AllRed)),
+        (40.0, (0, Interval::RedAmber)),
+        (106.0, (1, Interval::Green)),
+    ] {
+        assert_eq!(controller.state_at(time), state, "{} s", time);
+    }
+}
+
+#[test]
+fn cycle_length_stretches_the_greens() {
+    //70 s less 10 s of clearance leaves twice the green time asked for
+    let controller = FixedTimeController::new(plan(Some(70.0))).unwrap();
+    assert_eq!(controller.plan.cycle_length(), 70.0);
+    assert_eq!(controller.state_at(39.0), (0, Interval::Green));
+    assert_eq!(controller.state_at(40.0), (0, Interval::Amber));
+    assert_eq!(controller.state_at(45.0), (1, Interval::RedAmber));
+    assert_eq!(controller.state_at(46.0), (1, Interval::Green));
+}
+
+#[test]
+fn plans_without_green_time_are_rejected() {
+    let mut idle = plan(None);
+    idle.phase[1].duration = 0.0;
+    assert!(FixedTimeController::new(idle).is_err());
+    //a cycle no longer than the clearance times
+    assert!(FixedTimeController::new(plan(Some(10.0))).is_err());
+}
+
+//an empty crossing seconds into the run whose plan runs offset seconds behind
+fn at(seconds: f64, offset: f64) -> Observation {
+    Observation {
+        tick: (seconds * TICKS_PER_SECOND).round() as u64,
+        junction: 0,
+        approaching: HashMap::new(),
+        queues: HashMap::new(),
+        capacity: HashMap::new(),
+        waiting_times: HashMap::new(),
+        detections: vec![],
+        crossing_clear: true,
+        lights: HashMap::new(),
+        light_times: HashMap::new(),
+        pedestrians: HashMap::new(),
+        emergencies: vec![],
+        offset,
+    }
+}
+
+#[test]
+fn offset_plan_runs_behind_the_common_cycle() {
+    let mut controller = FixedTimeController::new(plan(None)).unwrap();
+    let east_west = Decision::Force(vec![Location::West, Location::East]);
+    let north_south = Decision::Force(vec![Location::North, Location::South]);
+    //east-west is still green 5 s after its green would have ended without the offset
+    assert_eq!(controller.decide(&at(24.0, 5.0)), east_west);
+    assert_eq!(controller.decide(&at(25.0, 5.0)), Decision::Force(vec![]));
+    assert_eq!(controller.decide(&at(31.0, 5.0)), north_south);
+    //before the offset has run out the plan is still at the end of the previous cycle
+    assert_eq!(controller.decide(&at(4.0, 5.0)), Decision::Force(vec![]));
+    assert_eq!(controller.state_at(4.0 - 5.0), (1, Interval::AllRed));
+}
+
+#[test]
+fn endless_durations_and_offsets_are_rejected() {
+    for time in [f64::NAN, f64::INFINITY] {
+        let mut endless = plan(None);
+        endless.phase[0].duration = time;
+        assert!(FixedTimeController::new(endless).is_err());
+        assert!(FixedTimeController::new(plan(Some(time))).is_err());
+        let mut offset = plan(None);
+        offset.offsets = vec![time];
+        assert!(FixedTimeController::new(offset).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use road_intersection::constants::*;
+use road_intersection::following::{CarFollowing, IdmParams};
+use road_intersection::model::{Destination, Location, Model};
+use road_intersection::signal::Decision;
+use road_intersection::simulation::Simulation;
+
+//speeds in pixels per second that never line up with whole pixels per tick
+const SPEEDS: [f64; 4] = [23.7, 41.3, 59.9, 83.1];
+
+fn followings() -> Vec<CarFollowing> {
+    let mut followings = vec![CarFollowing::Basic];
+    for speed in SPEEDS {
+        followings.push(CarFollowing::Idm(IdmParams {
+            desired_speed: speed,
+            ..IdmParams::default()
+        }));
+    }
+    followings
+}
+
+fn start_speed(following: &CarFollowing) -> f64 {
+    match following {
+        CarFollowing::Basic => 0.37,
+        CarFollowing::Idm(params) => params.desired_speed * TICK_DURATION,
+    }
+}
+
+//lights stay red, cars queue up in front of the stop line
+fn run_against_red(
+    following: &CarFollowing,
+    destination: Destination,
+    cars: usize,
+    ticks: u64,
+) -> (Simulation, usize) {
+    let mut simulation = Simulation::new(Model::with_seed(7));
+    simulation.model.config.following = following.clone();
+    let mut detections = 0;
+    let mut spawned = 0;
+    for _ in 0..ticks {
+        if spawned < cars
+            && simulation
+                .model
+                .spawn_car(Location::West, 0, destination.clone())
+        {
+            simulation.model.cars.last_mut().unwrap().speed = start_speed(following);
+            spawned += 1;
+        }
+        let observations = simulation.advance_with(&Decision::Keep);
+        detections += observations
+            .iter()
+            .map(|observation| observation.detections.len())
+            .sum::<usize>();
+        for car in &simulation.model.cars {
+            let distance = car.stop_line_distance().unwrap();
+            assert!(distance >= 0.0, "car ran the red light: {:?}", following);
+        }
+    }
+    (simulation, detections)
+}
+
+#[test]
+fn car_stops_at_the_stop_line_at_any_speed() {
+    for following in followings() {
+        let (simulation, _) = run_against_red(&following, Destination::Ahead, 1, 60 * 60);
+        let car = &simulation.model.cars[0];
+        assert!(car.is_stopped, "car never stopped: {:?}", following);
+        let distance = car.stop_line_distance().unwrap();
+        assert!(
+            distance < DETECTOR_LENGTH,
+            "car stopped {} px short of the line: {:?}",
+            distance,
+            following
+        );
+    }
+}
+
+#[test]
+fn queued_cars_keep_their_distance_at_any_speed() {
+    for following in followings() {
+        let (simulation, _) = run_against_red(&following, Destination::Ahead, 4, 60 * 120);
+        let min_gap = match &following {
+            CarFollowing::Basic => SEPARATION_DISTANCE,
+            CarFollowing::Idm(params) => params.min_gap,
+        };
+        let mut positions: Vec<f64> = simulation
+            .model
+            .cars
+            .iter()
+            .map(|car| car.position.x)
+            .collect();
+        assert_eq!(positions.len(), 4);
+        positions.sort_by(|a, b| b.total_cmp(a));
+        for pair in positions.windows(2) {
+            let gap = pair[0] - pair[1] - CAR_SIZE_F64;
+            assert!(
+                gap >= min_gap - POSITION_TOLERANCE,
+                "gap {} below {}: {:?}",
+                gap,
+                min_gap,
+                following
+            );
+            assert!(
+                gap < min_gap + CAR_SIZE_F64 / 2.0,
+                "queue did not close up, gap {}: {:?}",
+                gap,
+                following
+            );
+        }
+    }
+}
+
+#[test]
+fn every_detector_sees_a_car_once_at_any_speed() {
+    for following in followings() {
+        let (_, detections) = run_against_red(&following, Destination::Ahead, 1, 60 * 60);
+        assert_eq!(detections, 2, "{:?}", following);
+    }
+}
+
+#[test]
+fn turning_cars_stop_at_the_line_and_finish_the_turn_at_any_speed() {
+    for following in followings() {
+        for destination in [Destination::Left, Destination::Right] {
+            let (mut simulation, _) = run_against_red(&following, destination, 1, 60 * 60);
+            let distance = simulation.model.cars[0].stop_line_distance().unwrap();
+            assert!(distance < DETECTOR_LENGTH, "{:?}", following);
+            let green = Decision::Force(vec![Location::West]);
+            for _ in 0..60 * 60 {
+                simulation.advance_with(&green);
+            }
+            assert_eq!(simulation.throughput, 1, "{:?}", following);
+        }
+    }
+}
+
+#[test]
+fn spawning_respects_separation_at_fractional_positions() {
+    let mut model = Model::with_seed(7);
+    assert!(model.spawn_car(Location::West, 0, Destination::Ahead));
+    model.cars[0].position.x = CAR_SIZE_F64 + SEPARATION_DISTANCE - 0.25;
+    assert!(!model.spawn_car(Location::West, 0, Destination::Ahead));
+    model.cars[0].position.x = CAR_SIZE_F64 + SEPARATION_DISTANCE + 0.25;
+    assert!(model.spawn_car(Location::West, 0, Destination::Ahead));
+    //a car on the other side of the road does not block the entry
+    assert!(model.spawn_car(Location::East, 0, Destination::Ahead));
+}
+
+#[test]
+fn endless_idm_parameters_are_rejected() {
+    let setters: [fn(&mut IdmParams, f64); 6] = [
+        |params, value| params.desired_speed = value,
+        |params, value| params.time_headway = value,
+        |params, value| params.min_gap = value,
+        |params, value| params.max_acceleration = value,
+        |params, value| params.comfortable_deceleration = value,
+        |params, value| params.exponent = value,
+    ];
+    for value in [f64::NAN, f64::INFINITY] {
+        for (index, set) in setters.iter().enumerate() {
+            let mut params = IdmParams::default();
+            set(&mut params, value);
+            assert!(params.validate().is_err(), "{} {}", index, value);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use road_intersection::constants::*;
+use road_intersection::model::{Location, Model, SignalState};
+use road_intersection::scenario::Scenario;
+use road_intersection::signal::{green_wave, SignalConfig};
+use road_intersection::simulation::Simulation;
+
+const CORRIDOR: &str = r#"
+duration = 60.0
+
+[network]
+columns = 2
+
+[signal]
+controller = "fixed"
+offsets = [0.0, 5.0]
+
+[[signal.phase]]
+green = ["west", "east"]
+duration = 15.0
+
+[[signal.phase]]
+green = ["north", "south"]
+duration = 15.0
+"#;
+
+fn close(first: f64, second: f64, tolerance: f64) -> bool {
+    (first - second).abs() < tolerance
+}
+
+#[test]
+fn offsets_are_travel_times_within_the_cycle() {
+    let offsets = green_wave(&[0.0, 200.0, 400.0, 2000.0], 60.0, 30.0);
+    let expected = [0.0, 10.0 / 3.0, 20.0 / 3.0, 10.0 / 3.0];
+    for (offset, expected) in offsets.iter().zip(expected) {
+        assert!(close(*offset, expected, 1e-9), "{} {}", offset, expected);
+    }
+}
+
+#[test]
+fn green_wave_scenario_uses_the_computed_offsets() {
+    let scenario = Scenario::load("scenarios/green_wave.toml").unwrap();
+    let SignalConfig::Fixed(plan) = &scenario.signal else {
+        panic!("green wave scenario needs a fixed plan");
+    };
+    let network = &scenario.network;
+    let distances: Vec<f64> = (0..network.junctions())
+        .map(|index| network.distance_along(index, &Location::East))
+        .collect();
+    let speed = CAR_SPEED_F64 * TICKS_PER_SECOND;
+    let offsets = green_wave(&distances, speed, plan.cycle_length());
+    assert_eq!(offsets.len(), plan.offsets.len());
+    for (offset, configured) in offsets.iter().zip(&plan.offsets) {
+        //the scenario keeps one decimal
+        assert!(
+            close(*offset, *configured, 0.05),
+            "{} {}",
+            offset,
+            configured
+        );
+    }
+}
+
+#[test]
+fn offsets_outside_the_cycle_are_rejected() {
+    let text = CORRIDOR.replace("[0.0, 5.0]", "[0.0, 40.0]");
+    let scenario = Scenario::parse(&text).unwrap();
+    assert!(scenario.signal.build().is_err());
+}
+
+#[test]
+fn offset_crossing_turns_green_later() {
+    let scenario = Scenario::parse(CORRIDOR).unwrap();
+    let signal = scenario.signal.clone();
+    let mut simulation = Simulation::with_scenario(Model::with_seed(7), scenario);
+    simulation.traffic_controllers = simulation
+        .model
+        .junctions
+        .iter()
+        .map(|_| signal.build())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    simulation.model.set_timing(&signal.timing());
+    simulation.model.set_offsets(&signal.offsets());
+    //the first green of the north south phase at each crossing
+    let mut greens = [None, None];
+    while greens.iter().any(|green| green.is_none()) && !simulation.is_finished() {
+        simulation.advance();
+        for (junction, green) in simulation.model.junctions.iter().zip(greens.iter_mut()) {
+            let light = &junction.traffic_light_switch.traffic_lights[&Location::North];
+            if green.is_none() && light.state == SignalState::Green {
+                *green = Some(simulation.time());
+            }
+        }
+    }
+    let (Some(first), Some(second)) = (greens[0], greens[1]) else {
+        panic!("north south phase never turned green: {:?}", greens);
+    };
+    assert!(
+        close(second - first, 5.0, 2.0 * TICK_DURATION),
+        "{} {}",
+        first,
+        second
+    );
+}
@@ -0,0 +1,80 @@
+use road_intersection::metrics::Kpis;
+use road_intersection::model::{Location, SignalState};
+use road_intersection::signal::Observation;
+use std::collections::HashMap;
+
+//a tick with light on west in state and queue cars standing at its stop line
+fn tick(state: SignalState, queue: usize) -> Observation {
+    Observation {
+        tick: 0,
+        junction: 0,
+        offset: 0.0,
+        approaching: HashMap::from([(Location::West, queue)]),
+        queues: HashMap::from([(Location::West, queue)]),
+        capacity: HashMap::new(),
+        waiting_times: HashMap::new(),
+        detections: vec![],
+        crossing_clear: true,
+        lights: HashMap::from([(Location::West, state), (Location::North, SignalState::Red)]),
+        light_times: HashMap::new(),
+        pedestrians: HashMap::new(),
+        emergencies: vec![],
+    }
+}
+
+#[test]
+fn delay_percentile_takes_the_nearest_rank() {
+    let mut kpis = Kpis::default();
+    kpis.delays = (1..=20).rev().map(f64::from).collect();
+    assert_eq!(kpis.mean_delay(), 10.5);
+    assert_eq!(kpis.delay_percentile(95.0), 19.0);
+    assert_eq!(kpis.delay_percentile(50.0), 10.0);
+    assert_eq!(kpis.delay_percentile(100.0), 20.0);
+    assert_eq!(kpis.delay_percentile(0.0), 1.0);
+}
+
+#[test]
+fn no_trips_give_no_delay() {
+    let kpis = Kpis::default();
+    assert_eq!(kpis.mean_delay(), 0.0);
+    assert_eq!(kpis.delay_percentile(95.0), 0.0);
+}
+
+#[test]
+fn green_share_counts_the_ticks_on_green() {
+    let mut kpis = Kpis::default();
+    for _ in 0..30 {
+        kpis.record_tick(&[tick(SignalState::Green, 1)]);
+    }
+    for queue in 0..90 {
+        kpis.record_tick(&[tick(SignalState::Red, queue / 10)]);
+    }
+    assert_eq!(kpis.green_share(&Location::West), 0.25);
+    assert_eq!(kpis.green_share(&Location::North), 0.0);
+    assert_eq!(kpis.max_queue(&Location::West), 8);
+}
+
+#[test]
+fn table_lists_the_arms_of_the_network_in_line() {
+    let mut kpis = Kpis::default();
+    let mut observation = tick(SignalState::Green, 2);
+    observation.lights = [Location::West, Location::East, Location::NorthWest]
+        .into_iter()
+        .map(|location| (location, SignalState::Red))
+        .collect();
+    kpis.record_tick(&[observation]);
+    let summary = kpis.summary();
+    let table: Vec<&str> = summary
+        .lines()
+        .skip_while(|line| !line.starts_with("approach"))
+        .collect();
+    let names: Vec<&str> = table[1..]
+        .iter()
+        .map(|line| line.split_whitespace().next().unwrap())
+        .collect();
+    assert_eq!(names, ["west", "east", "northwest"]);
+    //every column ends where its heading does
+    for line in &table[1..] {
+        assert_eq!(line.len(), table[0].len(), "{}", line);
+    }
+}
@@ -0,0 +1,95 @@
+use road_intersection::model::Model;
+use road_intersection::scenario::Scenario;
+use road_intersection::simulation::Simulation;
+
+//trucks turning right across zebras with straight-on traffic behind them
+const TURNING_TRUCKS: &str = r#"
+duration = 300.0
+
+[layout]
+zebras = true
+
+[pedestrians]
+arrivals = { kind = "poisson", rate = 0.05 }
+
+[[generator]]
+from = "north"
+turn = "right"
+vehicle = "truck"
+arrivals = { kind = "poisson", rate = 0.1 }
+
+[[generator]]
+from = "north"
+turn = "ahead"
+arrivals = { kind = "poisson", rate = 0.2 }
+
+[[generator]]
+from = "east"
+arrivals = { kind = "poisson", rate = 0.2 }
+
+[[generator]]
+from = "south"
+arrivals = { kind = "poisson", rate = 0.2 }
+"#;
+
+//trucks and bicycles changing lanes between two crossings
+const MIXED_GRID: &str = r#"
+duration = 300.0
+
+[layout]
+lanes = [["left", "ahead"], ["right"]]
+zebras = true
+
+[network]
+columns = 2
+
+[pedestrians]
+arrivals = { kind = "poisson", rate = 0.05 }
+
+[[generator]]
+from = "north"
+vehicle = "truck"
+arrivals = { kind = "poisson", rate = 0.1 }
+
+[[generator]]
+from = "south"
+vehicle = "bicycle"
+arrivals = { kind = "poisson", rate = 0.1 }
+
+[signal]
+controller = "fixed"
+"#;
+
+//same wiring as the headless binary
+fn collisions(text: &str, seed: u64) -> usize {
+    let scenario = Scenario::parse(text).unwrap();
+    let signal = scenario.signal.clone();
+    let mut simulation = Simulation::with_scenario(Model::with_seed(seed), scenario);
+    simulation.traffic_controllers = simulation
+        .model
+        .junctions
+        .iter()
+        .map(|_| signal.build())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    simulation.model.set_timing(&signal.timing());
+    simulation.model.set_offsets(&signal.offsets());
+    while !simulation.is_finished() {
+        simulation.advance();
+    }
+    simulation.collisions.len()
+}
+
+#[test]
+fn cars_behind_a_turning_truck_do_not_run_into_it() {
+    for seed in [3, 11] {
+        assert_eq!(collisions(TURNING_TRUCKS, seed), 0, "seed {}", seed);
+    }
+}
+
+#[test]
+fn bicycles_do_not_change_lanes_into_a_truck() {
+    for seed in [41, 55] {
+        assert_eq!(collisions(MIXED_GRID, seed), 0, "seed {}", seed);
+    }
+}
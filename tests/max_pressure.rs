@@ -0,0 +1,99 @@
+use road_intersection::constants::*;
+use road_intersection::model::Location;
+use road_intersection::signal::{
+    Decision, MaxPressureConfig, MaxPressureController, Observation, SignalTiming,
+    TrafficController,
+};
+use std::collections::HashMap;
+
+//cars standing at each stop line after seconds, each having waited wait seconds
+fn queued(seconds: f64, queues: &[(Location, usize)], wait: f64) -> Observation {
+    let mut observation = Observation {
+        tick: (seconds * TICKS_PER_SECOND) as u64,
+        junction: 0,
+        offset: 0.0,
+        approaching: HashMap::new(),
+        queues: HashMap::new(),
+        capacity: HashMap::new(),
+        waiting_times: HashMap::new(),
+        detections: vec![],
+        crossing_clear: true,
+        lights: HashMap::new(),
+        light_times: HashMap::new(),
+        pedestrians: HashMap::new(),
+        emergencies: vec![],
+    };
+    for location in Location::compass() {
+        let queue = queues
+            .iter()
+            .find(|(side, _)| *side == location)
+            .map_or(0, |(_, queue)| *queue);
+        observation.approaching.insert(location.clone(), queue);
+        observation.queues.insert(location.clone(), queue);
+        observation
+            .waiting_times
+            .insert(location, queue as f64 * wait);
+    }
+    observation
+}
+
+fn controller() -> MaxPressureController {
+    MaxPressureController::new(&MaxPressureConfig {
+        timing: SignalTiming {
+            min_green: 10.0,
+            ..SignalTiming::default()
+        },
+    })
+    .unwrap()
+}
+
+//queues of opposing approaches add up, north and south outweigh west and east
+#[test]
+fn longest_queue_gets_green_with_its_opposite() {
+    let mut controller = controller();
+    let observation = queued(
+        0.0,
+        &[
+            (Location::West, 2),
+            (Location::North, 5),
+            (Location::East, 1),
+        ],
+        1.0,
+    );
+    assert_eq!(
+        controller.decide(&observation),
+        Decision::Force(vec![Location::North, Location::South])
+    );
+}
+
+#[test]
+fn green_is_held_for_the_minimum_time() {
+    let mut controller = controller();
+    controller.decide(&queued(0.0, &[(Location::North, 3)], 1.0));
+    let longer = [(Location::North, 1), (Location::West, 4)];
+    assert_eq!(
+        controller.decide(&queued(5.0, &longer, 1.0)),
+        Decision::Keep
+    );
+    assert_eq!(
+        controller.decide(&queued(10.0, &longer, 1.0)),
+        Decision::Force(vec![Location::West, Location::East])
+    );
+}
+
+#[test]
+fn equal_queues_go_to_the_longest_wait() {
+    let mut controller = controller();
+    let mut observation = queued(0.0, &[(Location::South, 2), (Location::East, 2)], 1.0);
+    observation.waiting_times.insert(Location::East, 9.0);
+    assert_eq!(
+        controller.decide(&observation),
+        Decision::Force(vec![Location::West, Location::East])
+    );
+}
+
+#[test]
+fn nothing_to_serve_keeps_the_lights() {
+    let mut controller = controller();
+    assert_eq!(controller.decide(&queued(0.0, &[], 0.0)), Decision::Keep);
+}
@@ -0,0 +1,52 @@
+use road_intersection::collision::Obb;
+use road_intersection::model::PointF;
+
+fn obb(x: f64, y: f64, deg: f64, length: f64, width: f64) -> Obb {
+    let (sin, cos) = deg.to_radians().sin_cos();
+    Obb {
+        center: PointF::new(x, y),
+        half_length: length / 2.0,
+        half_width: width / 2.0,
+        along: (cos, sin),
+        across: (-sin, cos),
+    }
+}
+
+#[test]
+fn boxes_that_only_touch_do_not_overlap() {
+    let first = obb(0.0, 0.0, 0.0, 30.0, 20.0);
+    assert!(!first.overlaps(&obb(30.0, 0.0, 0.0, 30.0, 20.0)));
+    assert!(!first.overlaps(&obb(0.0, 20.0, 0.0, 30.0, 20.0)));
+    assert!(first.overlaps(&obb(29.0, 0.0, 0.0, 30.0, 20.0)));
+}
+
+#[test]
+fn cars_side_by_side_in_their_lanes_do_not_overlap() {
+    let truck = obb(100.0, 16.0, 0.0, 60.0, 28.0);
+    let car = obb(110.0, 48.0, 0.0, 30.0, 20.0);
+    assert!(!truck.overlaps(&car));
+    assert!(!car.overlaps(&truck));
+}
+
+#[test]
+fn turned_boxes_overlap_only_where_they_really_meet() {
+    //the corners of the square boxes around them would touch, the cars themselves do not
+    let first = obb(0.0, 0.0, 45.0, 40.0, 10.0);
+    let second = obb(20.0, -20.0, 45.0, 40.0, 10.0);
+    assert!(!first.overlaps(&second));
+    //a car crossing the path of another
+    let crossing = obb(10.0, 0.0, 90.0, 40.0, 10.0);
+    assert!(first.overlaps(&crossing));
+    assert!(crossing.overlaps(&first));
+}
+
+#[test]
+fn corners_follow_the_heading() {
+    let corners = obb(10.0, 20.0, 90.0, 40.0, 10.0).corners();
+    //front left, front right, back right, back left of a box heading down the screen
+    let expected = [(15.0, 40.0), (5.0, 40.0), (5.0, 0.0), (15.0, 0.0)];
+    for (corner, (x, y)) in corners.iter().zip(expected) {
+        assert!((corner.x - x).abs() < 1e-9, "{} {}", corner.x, x);
+        assert!((corner.y - y).abs() < 1e-9, "{} {}", corner.y, y);
+    }
+}
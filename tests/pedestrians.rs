@@ -0,0 +1,55 @@
+use road_intersection::model::{Location, SignalState, TrafficLight, TrafficLightSwitch};
+use road_intersection::pedestrian::WalkSignal;
+use std::collections::HashMap;
+
+//lights of a crossroads, all red but the given ones
+fn lights(states: &[(Location, SignalState)]) -> HashMap<Location, TrafficLight> {
+    let mut lights = TrafficLightSwitch::create_traffic_lights(&Location::all());
+    for (location, state) in states {
+        lights.get_mut(location).unwrap().set_state(*state);
+    }
+    lights
+}
+
+#[test]
+fn pedestrian_phase_walks_every_arm_on_all_red() {
+    let lights = lights(&[]);
+    for side in Location::all() {
+        let signal = WalkSignal::of(&side, &lights, true, WalkSignal::DontWalk);
+        assert_eq!(signal, WalkSignal::Walk, "{}", side.name());
+    }
+    //without the phase and no green beside them people keep waiting
+    for side in Location::all() {
+        let signal = WalkSignal::of(&side, &lights, false, WalkSignal::DontWalk);
+        assert_eq!(signal, WalkSignal::DontWalk, "{}", side.name());
+    }
+}
+
+#[test]
+fn no_walk_while_cars_over_the_zebra_may_move() {
+    for state in [
+        SignalState::RedAmber,
+        SignalState::Green,
+        SignalState::Amber,
+    ] {
+        let lights = lights(&[(Location::North, state)]);
+        for side in [Location::North, Location::South] {
+            for previous in [WalkSignal::DontWalk, WalkSignal::Walk] {
+                let signal = WalkSignal::of(&side, &lights, true, previous);
+                assert_eq!(signal, WalkSignal::DontWalk, "{:?} {}", state, side.name());
+            }
+        }
+    }
+}
+
+#[test]
+fn walk_ends_in_clearance_until_the_lights_change() {
+    let lights = lights(&[]);
+    let signal = WalkSignal::of(&Location::West, &lights, false, WalkSignal::Walk);
+    assert_eq!(signal, WalkSignal::Clearance);
+    let signal = WalkSignal::of(&Location::West, &lights, false, WalkSignal::Clearance);
+    assert_eq!(signal, WalkSignal::Clearance);
+    let lights = self::lights(&[(Location::East, SignalState::RedAmber)]);
+    let signal = WalkSignal::of(&Location::West, &lights, false, WalkSignal::Clearance);
+    assert_eq!(signal, WalkSignal::DontWalk);
+}
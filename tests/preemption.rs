@@ -0,0 +1,88 @@
+use road_intersection::constants::*;
+use road_intersection::following::{CarFollowing, IdmParams};
+use road_intersection::model::{Destination, Location, Model};
+use road_intersection::network::{Edge, Route};
+use road_intersection::signal::Decision;
+use road_intersection::simulation::Simulation;
+use road_intersection::vehicle::VehicleKind;
+
+fn spawn(
+    simulation: &mut Simulation,
+    side: Location,
+    turn: Destination,
+    kind: VehicleKind,
+) -> bool {
+    let entry = Edge { side, road: 0 };
+    simulation
+        .model
+        .spawn_on_route(&entry, Route::turn(turn), kind)
+}
+
+//west and east stay green, a car from the west waits on its line to turn across the
+//oncoming stream when an ambulance from the north calls green for its own approach,
+//the stream stops on amber and leaves the car a gap it must not take
+fn run_preempted(ticks: u64) -> Simulation {
+    let mut simulation = Simulation::new(Model::with_seed(7));
+    simulation.model.config.following = CarFollowing::Idm(IdmParams::default());
+    let green = Decision::Force(vec![Location::West, Location::East]);
+    assert!(spawn(
+        &mut simulation,
+        Location::West,
+        Destination::Right,
+        VehicleKind::Car
+    ));
+    loop {
+        spawn(
+            &mut simulation,
+            Location::East,
+            Destination::Ahead,
+            VehicleKind::Car,
+        );
+        simulation.advance_with(&green);
+        let turner = &simulation.model.cars[0];
+        if turner.is_stopped && turner.stop_line_distance().unwrap() < DETECTOR_LENGTH {
+            break;
+        }
+    }
+    assert!(spawn(
+        &mut simulation,
+        Location::North,
+        Destination::Ahead,
+        VehicleKind::Emergency
+    ));
+    for _ in 0..ticks {
+        simulation.advance_with(&green);
+        let config = &simulation.model.config;
+        let cars = &simulation.model.cars;
+        let Some(ambulance) = cars.iter().find(|car| car.kind == VehicleKind::Emergency) else {
+            continue;
+        };
+        if ambulance.preemption_call().is_none() && !ambulance.is_in_crossing(config) {
+            continue;
+        }
+        for car in cars.iter().filter(|car| car.origin == Location::West) {
+            let distance = car.stop_line_distance().unwrap();
+            assert!(distance >= 0.0, "car entered ahead of the ambulance");
+        }
+    }
+    simulation
+}
+
+#[test]
+fn ambulance_gets_green_and_a_waiting_turner_holds() {
+    let simulation = run_preempted(60 * 20);
+    assert_eq!(simulation.kpis.preemption_calls, 1);
+    assert_eq!(simulation.kpis.preemptions.len(), 1);
+    assert!(
+        simulation.kpis.preemptions[0]
+            <= DEFAULT_AMBER_TIME + DEFAULT_ALL_RED_TIME + DEFAULT_RED_AMBER_TIME
+    );
+    assert!(simulation.collisions.is_empty());
+}
+
+#[test]
+fn held_cars_go_on_once_the_ambulance_is_through() {
+    let simulation = run_preempted(60 * 40);
+    assert!(simulation.model.cars.is_empty());
+    assert!(simulation.collisions.is_empty());
+}
@@ -0,0 +1,61 @@
+use road_intersection::model::{Location, Model};
+use road_intersection::scenario::Scenario;
+use road_intersection::simulation::Simulation;
+
+//a car from the east half a second behind one from the north that passes its entry on the ring
+fn scenario(with_ring_car: bool) -> Scenario {
+    let mut text = String::from(
+        r#"
+duration = 20.0
+
+[layout]
+junction = "roundabout"
+
+[[spawn]]
+time = 0.5
+from = "east"
+turn = "ahead"
+"#,
+    );
+    if with_ring_car {
+        text.push_str(
+            r#"
+[[spawn]]
+time = 0.0
+from = "north"
+turn = "ahead"
+"#,
+        );
+    }
+    Scenario::parse(&text).unwrap()
+}
+
+//seconds until the car from the east joined the ring and how often it stopped
+fn east_entry(with_ring_car: bool) -> (f64, u64) {
+    let mut simulation = Simulation::with_scenario(Model::with_seed(1), scenario(with_ring_car));
+    while !simulation.is_finished() {
+        simulation.advance();
+        assert!(simulation.collisions.is_empty());
+        if let Some(car) = simulation
+            .model
+            .cars
+            .iter()
+            .find(|car| car.origin == Location::East)
+        {
+            if car.ring.is_some() {
+                return (simulation.time(), car.stops);
+            }
+        }
+    }
+    panic!("car from the east never joined the ring");
+}
+
+#[test]
+fn entry_gives_way_to_the_ring() {
+    let (alone, alone_stops) = east_entry(false);
+    let (behind, stops) = east_entry(true);
+    assert_eq!(alone_stops, 0);
+    assert_eq!(stops, 1);
+    //the car on the ring passes first, its length and the separation take more than a second
+    assert!(behind > alone + 1.0, "{} {}", alone, behind);
+}
@@ -0,0 +1,60 @@
+use road_intersection::model::{Destination, Location};
+use road_intersection::network::{Edge, Network, Route};
+
+//two by two crossings, cars come in on the top road from the west
+fn route_to(side: Location, road: usize) -> Option<Route> {
+    let network = Network {
+        columns: 2,
+        rows: 2,
+        ..Network::default()
+    };
+    assert_eq!(network.entry(&Location::West, 0), Some(0));
+    network.route(0, &Location::East, &Edge { side, road })
+}
+
+fn corners(route: &Route) -> usize {
+    route
+        .turns
+        .iter()
+        .filter(|turn| **turn != Destination::Ahead)
+        .count()
+}
+
+#[test]
+fn straight_through_needs_no_turn() {
+    let route = route_to(Location::East, 0).unwrap();
+    assert_eq!(route.turns, [Destination::Ahead, Destination::Ahead]);
+    assert_eq!(
+        route.goal,
+        Some(Edge {
+            side: Location::East,
+            road: 0
+        })
+    );
+}
+
+#[test]
+fn nearest_exit_turns_at_the_first_crossing() {
+    let south = route_to(Location::South, 0).unwrap();
+    assert_eq!(south.turns, [Destination::Right, Destination::Ahead]);
+    let north = route_to(Location::North, 1).unwrap();
+    assert_eq!(north.turns, [Destination::Ahead, Destination::Left]);
+}
+
+#[test]
+fn parallel_road_is_reached_over_one_block() {
+    let route = route_to(Location::East, 1).unwrap();
+    assert_eq!(route.turns.len(), 3);
+    assert_eq!(corners(&route), 2);
+}
+
+#[test]
+fn way_back_goes_round_the_shortest_block() {
+    let route = route_to(Location::West, 1).unwrap();
+    assert_eq!(route.turns, [Destination::Right, Destination::Right]);
+}
+
+#[test]
+fn missing_road_has_no_route() {
+    assert!(route_to(Location::East, 5).is_none());
+}
@@ -0,0 +1,118 @@
+use road_intersection::metrics::{write_trips, Trip};
+use road_intersection::model::{Destination, Location};
+use road_intersection::vehicle::VehicleKind;
+use std::collections::HashMap;
+use std::fs;
+
+fn trips() -> Vec<Trip> {
+    vec![
+        Trip {
+            id: 1,
+            origin: Location::West,
+            exit: Location::North,
+            turn: Destination::Left,
+            spawn_tick: 60,
+            exit_tick: 660,
+            stopped_ticks: 120,
+            stops: 1,
+            crossing_ticks: 90,
+            delay_ticks: 240,
+            vehicle: VehicleKind::Bicycle,
+        },
+        Trip {
+            id: 2,
+            origin: Location::South,
+            exit: Location::North,
+            turn: Destination::Ahead,
+            spawn_tick: 100,
+            exit_tick: 400,
+            stopped_ticks: 0,
+            stops: 0,
+            crossing_ticks: 30,
+            delay_ticks: 0,
+            vehicle: VehicleKind::Car,
+        },
+    ]
+}
+
+//file of its own in the temp directory so tests running side by side do not share one
+fn path(name: &str) -> String {
+    let file = format!("road_intersection_{}_{}", std::process::id(), name);
+    std::env::temp_dir()
+        .join(file)
+        .to_string_lossy()
+        .into_owned()
+}
+
+//field name to value for every trip written to path, the json records are flat
+//and hold no commas inside their values
+fn read(path: &str) -> Vec<HashMap<String, String>> {
+    let text = fs::read_to_string(path).unwrap();
+    fs::remove_file(path).unwrap();
+    let mut lines = text.lines();
+    if path.ends_with(".csv") {
+        let header: Vec<_> = lines.next().unwrap().split(',').collect();
+        lines
+            .map(|line| {
+                header
+                    .iter()
+                    .zip(line.split(','))
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect()
+            })
+            .collect()
+    } else {
+        lines
+            .map(|line| {
+                line.trim_matches(|c| c == '{' || c == '}')
+                    .split(',')
+                    .map(|field| {
+                        let (name, value) = field.split_once(':').unwrap();
+                        (
+                            name.trim_matches('"').to_string(),
+                            value.trim_matches('"').to_string(),
+                        )
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn csv_holds_every_trip() {
+    let path = path("trips.csv");
+    write_trips(&path, &trips()).unwrap();
+    let records = read(&path);
+    assert_eq!(records.len(), 2);
+    let first = &records[0];
+    assert_eq!(first["id"], "1");
+    assert_eq!(first["origin"], "west");
+    assert_eq!(first["turn"], "left");
+    assert_eq!(first["spawn_tick"], "60");
+    assert_eq!(first["exit_tick"], "660");
+    assert_eq!(first["travel_time"], "10.000");
+    assert_eq!(first["stopped_time"], "2.000");
+    assert_eq!(first["crossing_time"], "1.500");
+    assert_eq!(first["delay"], "4.000");
+    assert_eq!(first["stops"], "1");
+    assert_eq!(first["vehicle"], "bicycle");
+    assert_eq!(records[1]["origin"], "south");
+    assert_eq!(records[1]["vehicle"], "car");
+}
+
+#[test]
+fn json_lines_hold_the_same_as_csv() {
+    let (csv, json) = (path("same.csv"), path("same.jsonl"));
+    write_trips(&csv, &trips()).unwrap();
+    write_trips(&json, &trips()).unwrap();
+    assert_eq!(read(&json), read(&csv));
+}
+
+#[test]
+fn unwritable_path_is_an_error() {
+    let missing = std::env::temp_dir()
+        .join("road_intersection_missing")
+        .join("trips.csv");
+    assert!(write_trips(&missing.to_string_lossy(), &trips()).is_err());
+}